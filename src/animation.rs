@@ -1,31 +1,38 @@
 use std::{
-    cell::RefCell,
+    borrow::Cow,
+    cell::{Cell, RefCell},
     fmt,
+    future::Future,
     pin::Pin,
     rc::Rc,
     sync::{Arc, Mutex, Weak},
     task::{Context, Poll, Waker},
 };
 
-use discard::DiscardOnDrop;
 use futures_signals::{
     signal::{Mutable, MutableSignal, Signal, SignalExt, WaitFor},
     signal_vec::{SignalVec, VecDiff},
-    CancelableFutureHandle,
 };
-use futures_util::future::{ready, FutureExt};
+use futures_util::future::FutureExt;
+use js_sys::{Array, Object};
 use pin_project::pin_project;
-use wasm_bindgen::{closure::Closure, JsCast, UnwrapThrowExt};
-use web_sys::window;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue, UnwrapThrowExt};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{window, Animation, Element};
 
-use crate::{operations::spawn_future, utils::UnwrapJsExt};
+pub use web_sys::{FillMode, PlaybackDirection};
+
+use crate::{
+    bindings,
+    dom::EventOptions,
+    utils::{EventListener, UnwrapJsExt},
+};
 
 struct RafState {
     id: i32,
     closure: Closure<dyn FnMut(f64)>,
 }
 
-// TODO generalize this so it works for any target, not just JS
 // TODO move this into gloo
 struct Raf {
     state: Rc<RefCell<Option<RafState>>>,
@@ -80,17 +87,250 @@ impl Drop for Raf {
     }
 }
 
+/// The source of the frames for [`timestamps`] (and therefore all animations).
+///
+/// The default clock is [`RafClock`], it can be changed with [`set_clock`].
+pub trait Clock {
+    /// Starts calling `on_frame` once per frame, with the current time in milliseconds.
+    fn start(&self, on_frame: Box<dyn FnMut(f64)>);
+
+    /// Stops calling the `on_frame` callback which was passed to [`start`](Clock::start).
+    fn stop(&self);
+}
+
+/// A [`Clock`] which uses `requestAnimationFrame`.
+#[derive(Default)]
+pub struct RafClock {
+    raf: RefCell<Option<Raf>>,
+}
+
+impl RafClock {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl fmt::Debug for RafClock {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("RafClock")
+            .field("running", &self.raf.borrow().is_some())
+            .finish()
+    }
+}
+
+impl Clock for RafClock {
+    fn start(&self, on_frame: Box<dyn FnMut(f64)>) {
+        // The time doesn't move forward while the page is hidden
+        PAGE_VISIBLE.with(|visible| visible.init());
+
+        *self.raf.borrow_mut() = Some(Raf::new(on_frame));
+    }
+
+    fn stop(&self) {
+        // The Raf is dropped after the borrow ends
+        let raf = self.raf.borrow_mut().take();
+        drop(raf);
+    }
+}
+
+type OnFrame = Box<dyn FnMut(f64)>;
+
+type FrameCallback = Rc<RefCell<dyn FnMut(f64)>>;
+
+#[derive(Default)]
+struct ManualClockState {
+    time: Cell<f64>,
+    // This is used to detect whether the clock was stopped while it was running `on_frame`
+    generation: Cell<u32>,
+    on_frame: RefCell<Option<OnFrame>>,
+}
+
+/// A [`Clock`] which only moves forward when [`advance`](ManualClock::advance) is called.
+///
+/// This makes animations deterministic, which is useful for tests:
+///
+/// ```rust
+/// let clock = ManualClock::new();
+/// set_clock(clock.clone());
+///
+/// let animation = MutableAnimation::new(1000.0);
+/// animation.animate_to(Percentage::END);
+///
+/// // The first frame is the start of the animation
+/// clock.advance(0.0);
+/// clock.advance(500.0);
+///
+/// assert_eq!(animation.current_percentage(), Percentage::new(0.5));
+/// ```
+#[derive(Clone, Default)]
+pub struct ManualClock {
+    state: Rc<ManualClockState>,
+}
+
+impl ManualClock {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current time in milliseconds, it starts at `0.0`.
+    #[inline]
+    pub fn now(&self) -> f64 {
+        self.state.time.get()
+    }
+
+    /// Moves the time forward by `duration` milliseconds, and then runs a single frame.
+    ///
+    /// Animations are updated before this returns, but anything which listens to
+    /// their signals (such as `style_signal`) is only updated when the futures run.
+    pub fn advance(&self, duration: f64) {
+        debug_assert!(duration >= 0.0);
+
+        let time = self.state.time.get() + duration;
+        self.state.time.set(time);
+
+        let on_frame = self.state.on_frame.borrow_mut().take();
+
+        if let Some(mut on_frame) = on_frame {
+            let generation = self.state.generation.get();
+
+            on_frame(time);
+
+            if self.state.generation.get() == generation {
+                *self.state.on_frame.borrow_mut() = Some(on_frame);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ManualClock {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("ManualClock")
+            .field("time", &self.state.time.get())
+            .finish()
+    }
+}
+
+impl Clock for ManualClock {
+    fn start(&self, on_frame: Box<dyn FnMut(f64)>) {
+        self.state
+            .generation
+            .set(self.state.generation.get().wrapping_add(1));
+        *self.state.on_frame.borrow_mut() = Some(on_frame);
+    }
+
+    fn stop(&self) {
+        self.state
+            .generation
+            .set(self.state.generation.get().wrapping_add(1));
+
+        // The callback is dropped after the borrow ends
+        let on_frame = self.state.on_frame.borrow_mut().take();
+        drop(on_frame);
+    }
+}
+
 struct TimestampsManager {
-    raf: Option<Raf>,
+    clock: Rc<dyn Clock>,
+    running: bool,
+    time_scale: f64,
+    // The time of the previous frame, according to the clock
+    clock_time: Option<f64>,
+    // The time which is sent to the timestamps, it is affected by the time scale
+    time: Option<f64>,
     // TODO make this more efficient
     states: Vec<Weak<Mutex<TimestampsState>>>,
+    // These are called synchronously on every frame, they are used by `OnTimestampDiff`
+    callbacks: Vec<(u32, FrameCallback)>,
+    next_callback_id: u32,
 }
 
 impl TimestampsManager {
     fn new() -> Self {
         Self {
-            raf: None,
+            clock: Rc::new(RafClock::new()),
+            running: false,
+            time_scale: 1.0,
+            clock_time: None,
+            time: None,
             states: vec![],
+            callbacks: vec![],
+            next_callback_id: 0,
+        }
+    }
+
+    fn start(&mut self, timestamps_manager: &Rc<RefCell<TimestampsManager>>) {
+        if !self.running {
+            self.running = true;
+
+            let timestamps_manager = timestamps_manager.clone();
+
+            self.clock.start(Box::new(move |clock_time| {
+                let mut lock = timestamps_manager.borrow_mut();
+
+                let is_visible = PAGE_VISIBLE.with(|visible| visible.is_visible());
+
+                let time = match (lock.time, lock.clock_time) {
+                    (Some(time), Some(previous)) if is_visible => {
+                        time + (clock_time - previous) * lock.time_scale
+                    }
+                    (Some(time), _) => time,
+                    (None, _) => clock_time,
+                };
+
+                lock.time = Some(time);
+                lock.clock_time = Some(clock_time);
+
+                lock.states.retain(|state| {
+                    if let Some(state) = state.upgrade() {
+                        let mut lock = state.lock().unwrap_throw();
+
+                        lock.changed = true;
+                        lock.value = Some(time);
+
+                        if let Some(waker) = lock.waker.take() {
+                            drop(lock);
+                            waker.wake();
+                        }
+
+                        true
+                    } else {
+                        false
+                    }
+                });
+
+                let callbacks: Vec<_> = lock
+                    .callbacks
+                    .iter()
+                    .map(|(_, callback)| Rc::downgrade(callback))
+                    .collect();
+
+                if lock.states.is_empty() && lock.callbacks.is_empty() {
+                    lock.stop();
+                    // TODO is this a good idea ?
+                    lock.states = vec![];
+                }
+
+                // The lock is released first, because the callbacks can start or stop animations
+                drop(lock);
+
+                for callback in callbacks {
+                    // The callback is skipped if it was removed by an earlier callback
+                    if let Some(callback) = callback.upgrade() {
+                        callback.borrow_mut()(time);
+                    }
+                }
+            }));
+        }
+    }
+
+    fn stop(&mut self) {
+        if self.running {
+            self.running = false;
+            // The time which passes while it is stopped is ignored
+            self.clock_time = None;
+            self.clock.stop();
         }
     }
 }
@@ -156,41 +396,214 @@ pub fn timestamps() -> Timestamps {
             let mut lock = timestamps_manager.borrow_mut();
 
             lock.states.push(Arc::downgrade(&timestamps.state));
+            lock.start(timestamps_manager);
+        }
 
-            if let None = lock.raf {
-                let timestamps_manager = timestamps_manager.clone();
+        timestamps
+    })
+}
 
-                lock.raf = Some(Raf::new(move |time| {
-                    let mut lock = timestamps_manager.borrow_mut();
+struct PageVisible {
+    // This is created lazily, because it needs the browser
+    visible: RefCell<Option<Mutable<bool>>>,
+}
 
-                    lock.states.retain(|state| {
-                        if let Some(state) = state.upgrade() {
-                            let mut lock = state.lock().unwrap_throw();
+impl PageVisible {
+    fn init(&self) -> Mutable<bool> {
+        self.visible
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                let visible = Mutable::new(!bindings::is_hidden());
+
+                // This is leaked because it is needed forever
+                let _ = bindings::DOCUMENT.with(|document| {
+                    EventListener::new(document, "visibilitychange", &EventOptions::default(), {
+                        let visible = visible.clone();
+                        move |_| {
+                            visible.set_neq(!bindings::is_hidden());
+
+                            // The time which passes while the page is hidden is ignored
+                            TIMESTAMPS_MANAGER.with(|timestamps_manager| {
+                                timestamps_manager.borrow_mut().clock_time = None;
+                            });
+                        }
+                    })
+                });
 
-                            lock.changed = true;
-                            lock.value = Some(time);
+                visible
+            })
+            .clone()
+    }
 
-                            if let Some(waker) = lock.waker.take() {
-                                drop(lock);
-                                waker.wake();
-                            }
+    fn is_visible(&self) -> bool {
+        match *self.visible.borrow() {
+            Some(ref visible) => visible.get(),
+            None => true,
+        }
+    }
+}
+
+thread_local! {
+    static PAGE_VISIBLE: PageVisible = const {
+        PageVisible {
+            visible: RefCell::new(None),
+        }
+    };
+}
+
+/// Returns `true` if the page is visible, and `false` if it is hidden (such as
+/// when the user switches to a different tab).
+///
+/// While the page is hidden, animations are paused, and they continue from
+/// where they were when the page becomes visible again.
+pub fn page_visible() -> impl Signal<Item = bool> {
+    PAGE_VISIBLE.with(|visible| visible.init()).signal()
+}
+
+/// Changes the [`Clock`] which is used by [`timestamps`] (and therefore all animations).
+///
+/// Existing animations continue from where they are, using the new clock.
+pub fn set_clock<C>(clock: C)
+where
+    C: Clock + 'static,
+{
+    TIMESTAMPS_MANAGER.with(|timestamps_manager| {
+        let mut lock = timestamps_manager.borrow_mut();
+
+        let running = lock.running;
+
+        lock.stop();
+        lock.clock = Rc::new(clock);
+
+        if running {
+            lock.start(timestamps_manager);
+        }
+    })
+}
+
+/// Changes the speed of all animations.
+///
+/// `1.0` is normal speed, `0.5` is half speed, `0.0` freezes all animations, etc.
+///
+/// This is useful for debugging animations, or for taking screenshots.
+pub fn set_time_scale(time_scale: f64) {
+    debug_assert!(time_scale >= 0.0);
+
+    TIMESTAMPS_MANAGER.with(|timestamps_manager| {
+        timestamps_manager.borrow_mut().time_scale = time_scale;
+    })
+}
 
-                            true
-                        } else {
-                            false
+/// Whether animations should respect the user's reduced motion preference.
+///
+/// This is set with [`set_reduced_motion`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReducedMotion {
+    /// Uses the [`prefers-reduced-motion`](https://developer.mozilla.org/en-US/docs/Web/CSS/@media/prefers-reduced-motion)
+    /// media query. This is the default.
+    System,
+    /// Always reduces motion.
+    Reduce,
+    /// Never reduces motion.
+    NoPreference,
+}
+
+struct ReducedMotionState {
+    setting: Mutable<ReducedMotion>,
+    scale: Cell<f64>,
+    // This is created lazily, because it needs the browser
+    system: RefCell<Option<Mutable<bool>>>,
+}
+
+impl ReducedMotionState {
+    fn system(&self) -> Mutable<bool> {
+        self.system
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                if let Some(query) = bindings::match_media("(prefers-reduced-motion: reduce)") {
+                    let value = Mutable::new(query.matches());
+
+                    // This is leaked because it is needed forever
+                    let _ = EventListener::new(&query, "change", &EventOptions::default(), {
+                        let value = value.clone();
+                        let query = query.clone();
+                        move |_| {
+                            value.set_neq(query.matches());
                         }
                     });
 
-                    if lock.states.len() == 0 {
-                        lock.raf = None;
-                        // TODO is this a good idea ?
-                        lock.states = vec![];
-                    }
-                }));
+                    value
+                } else {
+                    Mutable::new(false)
+                }
+            })
+            .clone()
+    }
+
+    fn is_reduced(&self) -> bool {
+        match self.setting.get() {
+            ReducedMotion::System => self.system().get(),
+            ReducedMotion::Reduce => true,
+            ReducedMotion::NoPreference => false,
+        }
+    }
+}
+
+thread_local! {
+    static REDUCED_MOTION: ReducedMotionState = ReducedMotionState {
+        setting: Mutable::new(ReducedMotion::System),
+        scale: Cell::new(0.0),
+        system: RefCell::new(None),
+    };
+}
+
+/// Changes whether animations reduce their motion, the default is [`ReducedMotion::System`].
+///
+/// When motion is reduced, the duration of [`MutableAnimation`] (and everything
+/// which uses it, such as [`animated_map`](AnimatedSignalVec::animated_map)),
+/// [`Timeline`], [`WebAnimation`], and FLIP animations is multiplied by the
+/// [reduced motion scale](set_reduced_motion_scale). [`SpringAnimation`] runs
+/// faster by the same amount.
+///
+/// Animations which are essential can opt out with [`MutableAnimation::set_essential`],
+/// [`SpringAnimation::set_essential`], [`Timeline::set_essential`], or the
+/// `essential` option of [`WebAnimationOptions`] and [`FlipOptions`](crate::FlipOptions).
+pub fn set_reduced_motion(setting: ReducedMotion) {
+    REDUCED_MOTION.with(|state| state.setting.set_neq(setting))
+}
+
+/// Changes how much the duration of animations is multiplied by when motion is reduced.
+///
+/// The default is `0.0`, which means that animations jump immediately to the end.
+pub fn set_reduced_motion_scale(scale: f64) {
+    debug_assert!(scale >= 0.0);
+
+    REDUCED_MOTION.with(|state| state.scale.set(scale))
+}
+
+/// Returns `true` if animations should reduce their motion.
+pub fn reduced_motion_signal() -> impl Signal<Item = bool> {
+    REDUCED_MOTION.with(|state| {
+        futures_signals::map_ref! {
+            let setting = state.setting.signal(),
+            let system = state.system().signal() =>
+            match setting {
+                ReducedMotion::System => *system,
+                ReducedMotion::Reduce => true,
+                ReducedMotion::NoPreference => false,
             }
         }
+    })
+}
 
-        timestamps
+// The amount that durations are multiplied by
+pub(crate) fn reduced_motion_scale() -> f64 {
+    REDUCED_MOTION.with(|state| {
+        if state.is_reduced() {
+            state.scale.get()
+        } else {
+            1.0
+        }
     })
 }
 
@@ -221,7 +634,7 @@ impl<S: SignalVec> AnimatedSignalVec for S {
 }
 
 #[derive(Debug)]
-pub struct AnimatedMapBroadcaster(MutableAnimation);
+pub struct AnimatedMapBroadcaster(pub(crate) MutableAnimation);
 
 impl AnimatedMapBroadcaster {
     // TODO it should return a custom type
@@ -627,22 +1040,63 @@ pub fn timestamps_difference() -> impl Signal<Item = Option<f64>> {
     })
 }
 
-pub struct OnTimestampDiff(DiscardOnDrop<CancelableFutureHandle>);
+/// Calls `callback` on every frame with the number of milliseconds since the
+/// first frame, until it is dropped.
+///
+/// This is the same as [`timestamps_absolute_difference`], except the callback
+/// is called synchronously inside of the frame, so animations are updated
+/// immediately by [`ManualClock::advance`].
+///
+/// The timestamps manager is not borrowed while the callback runs, so the
+/// callback can start or stop animations, and it can drop its own
+/// `OnTimestampDiff`.
+///
+/// But the callback must not use a [`RefCell`] which is already mutably
+/// borrowed when the frame runs. For example, calling [`ManualClock::advance`]
+/// while holding a `borrow_mut()` which is also used by the callback will
+/// panic.
+pub struct OnTimestampDiff(u32);
 
 impl OnTimestampDiff {
     pub fn new<F>(mut callback: F) -> Self
     where
         F: FnMut(f64) + 'static,
     {
-        OnTimestampDiff(spawn_future(timestamps_absolute_difference().for_each(
-            move |diff| {
-                if let Some(diff) = diff {
-                    callback(diff);
-                }
+        let mut starting_time = None;
+
+        let callback: FrameCallback = Rc::new(RefCell::new(move |time| {
+            let starting_time = *starting_time.get_or_insert(time);
+            callback(time - starting_time);
+        }));
+
+        TIMESTAMPS_MANAGER.with(|timestamps_manager| {
+            let mut lock = timestamps_manager.borrow_mut();
+
+            let id = lock.next_callback_id;
+            lock.next_callback_id = id.wrapping_add(1);
 
-                ready(())
-            },
-        )))
+            lock.callbacks.push((id, callback));
+            lock.start(timestamps_manager);
+
+            OnTimestampDiff(id)
+        })
+    }
+}
+
+impl Drop for OnTimestampDiff {
+    fn drop(&mut self) {
+        // This is ignored if the thread is being destroyed
+        let callback = TIMESTAMPS_MANAGER.try_with(|timestamps_manager| {
+            let mut lock = timestamps_manager.borrow_mut();
+
+            lock.callbacks
+                .iter()
+                .position(|(id, _)| *id == self.0)
+                .map(|index| lock.callbacks.remove(index))
+        });
+
+        // The callback is dropped after the borrow ends
+        drop(callback);
     }
 }
 
@@ -668,6 +1122,8 @@ impl Signal for MutableAnimationSignal {
 struct MutableAnimationState {
     playing: bool,
     duration: f64,
+    // Essential animations ignore the reduced motion setting
+    essential: bool,
     end: Percentage,
     _animating: Option<OnTimestampDiff>,
 }
@@ -692,6 +1148,7 @@ impl fmt::Debug for MutableAnimation {
         fmt.debug_struct("MutableAnimation")
             .field("playing", &state.playing)
             .field("duration", &state.duration)
+            .field("essential", &state.essential)
             .field("current", &self.inner.value.get())
             .field("end", &state.end)
             .finish()
@@ -708,6 +1165,7 @@ impl MutableAnimation {
                 state: Mutex::new(MutableAnimationState {
                     playing: true,
                     duration: duration,
+                    essential: false,
                     end: initial,
                     _animating: None,
                 }),
@@ -728,6 +1186,15 @@ impl MutableAnimation {
         }
     }
 
+    // The duration after taking reduced motion into account
+    fn duration(lock: &MutableAnimationState) -> f64 {
+        if lock.essential {
+            lock.duration
+        } else {
+            lock.duration * reduced_motion_scale()
+        }
+    }
+
     #[inline]
     fn stop_animating(lock: &mut MutableAnimationState) {
         lock._animating = None;
@@ -740,8 +1207,10 @@ impl MutableAnimation {
             let end: f64 = lock.end.into_f64();
 
             if start != end {
-                if lock.duration > 0.0 {
-                    let duration = (end - start).abs() * lock.duration;
+                let duration = Self::duration(lock);
+
+                if duration > 0.0 {
+                    let duration = (end - start).abs() * duration;
 
                     let state = self.raw_clone();
 
@@ -784,6 +1253,15 @@ impl MutableAnimation {
         }
     }
 
+    /// Marks the animation as essential, which means that it ignores
+    /// [reduced motion](set_reduced_motion).
+    ///
+    /// This should only be used when the motion is necessary to understand the page.
+    pub fn set_essential(&self, essential: bool) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+        lock.essential = essential;
+    }
+
     #[inline]
     pub fn pause(&self) {
         let mut lock = self.inner.state.lock().unwrap_throw();
@@ -826,7 +1304,7 @@ impl MutableAnimation {
         let mut lock = self.inner.state.lock().unwrap_throw();
 
         if lock.end != end {
-            if lock.duration <= 0.0 {
+            if Self::duration(&lock) <= 0.0 {
                 Self::_jump_to(&mut lock, &self.inner.value, end);
             } else {
                 lock.end = end;
@@ -846,78 +1324,1210 @@ impl MutableAnimation {
     }
 }
 
-pub mod easing {
-    use super::Percentage;
+#[derive(Debug)]
+pub struct SpringAnimationSignal(MutableSignal<f64>);
+
+impl Signal for SpringAnimationSignal {
+    type Item = f64;
 
-    // TODO should this use map rather than map_unchecked ?
     #[inline]
-    pub fn powi(p: Percentage, n: i32) -> Percentage {
-        p.map_unchecked(|p| p.powi(n))
+    fn poll_change(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.0.poll_change_unpin(cx)
+    }
+}
+
+struct SpringAnimationState {
+    stiffness: f64,
+    damping: f64,
+    mass: f64,
+    target: f64,
+    // In units per second
+    velocity: f64,
+    // The time of the previous frame
+    previous: f64,
+    essential: bool,
+    _animating: Option<OnTimestampDiff>,
+}
+
+struct SpringAnimationInner {
+    state: Mutex<SpringAnimationState>,
+    value: Mutable<f64>,
+}
+
+/// An animation which uses spring physics instead of a fixed duration.
+///
+/// When the target is changed while it is animating, the current velocity is
+/// preserved, so it smoothly changes direction. This makes it a good fit for
+/// interruptible animations (such as releasing a drag):
+///
+/// ```rust
+/// let spring = SpringAnimation::new(0.0);
+///
+/// // When the drag is released
+/// spring.set_velocity(velocity);
+/// spring.animate_to(100.0);
+///
+/// html!("div", {
+///     .style_signal("transform", spring.signal().map(|x| format!("translateX({}px)", x)))
+/// })
+/// ```
+#[derive(Clone)]
+pub struct SpringAnimation {
+    inner: Arc<SpringAnimationInner>,
+}
+
+impl fmt::Debug for SpringAnimation {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let state = self.inner.state.lock().unwrap_throw();
+
+        fmt.debug_struct("SpringAnimation")
+            .field("stiffness", &state.stiffness)
+            .field("damping", &state.damping)
+            .field("mass", &state.mass)
+            .field("current", &self.inner.value.get())
+            .field("target", &state.target)
+            .field("velocity", &state.velocity)
+            .field("essential", &state.essential)
+            .finish()
     }
+}
 
+impl SpringAnimation {
+    // The simulation uses small steps so that it is stable even with stiff springs
+    const STEP: f64 = 1.0 / 240.0;
+    // The maximum time for a single frame, so it doesn't jump after the page is hidden
+    const MAX_FRAME: f64 = 0.064;
+    const PRECISION: f64 = 0.001;
+
+    /// Creates a spring with the default stiffness (`170.0`), damping (`26.0`)
+    /// and mass (`1.0`).
     #[inline]
-    pub fn cubic(p: Percentage) -> Percentage {
-        powi(p, 3)
+    pub fn new(initial: f64) -> Self {
+        Self::new_with_config(initial, 170.0, 26.0, 1.0)
+    }
+
+    pub fn new_with_config(initial: f64, stiffness: f64, damping: f64, mass: f64) -> Self {
+        debug_assert!(stiffness > 0.0);
+        debug_assert!(damping >= 0.0);
+        debug_assert!(mass > 0.0);
+
+        Self {
+            inner: Arc::new(SpringAnimationInner {
+                state: Mutex::new(SpringAnimationState {
+                    stiffness,
+                    damping,
+                    mass,
+                    target: initial,
+                    velocity: 0.0,
+                    previous: 0.0,
+                    essential: false,
+                    _animating: None,
+                }),
+                value: Mutable::new(initial),
+            }),
+        }
     }
 
     #[inline]
-    pub fn out<F>(p: Percentage, f: F) -> Percentage
-    where
-        F: FnOnce(Percentage) -> Percentage,
-    {
-        f(p.invert()).invert()
+    fn raw_clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
     }
 
-    pub fn in_out<F>(p: Percentage, f: F) -> Percentage
-    where
-        F: FnOnce(Percentage) -> Percentage,
-    {
-        p.map_unchecked(|p| {
-            if p <= 0.5 {
-                f(Percentage::new_unchecked(p * 2.0)).into_f64() / 2.0
-            } else {
-                1.0 - (f(Percentage::new_unchecked((1.0 - p) * 2.0)).into_f64() / 2.0)
-            }
-        })
+    fn is_resting(lock: &SpringAnimationState, value: f64) -> bool {
+        lock.velocity.abs() < Self::PRECISION && (lock.target - value).abs() < Self::PRECISION
     }
 
-    // pub struct Point {
-    // pub x: f64,
-    // pub y: f64,
-    // }
+    fn step(lock: &mut SpringAnimationState, mut value: f64, time: f64) -> f64 {
+        let steps = (time / Self::STEP).ceil();
+        let step = time / steps;
 
-    // impl Point {
-    // fn range_inclusive(percentage: f64, from: &Self, to: &Self) -> Self {
-    // Point {
-    // x: range_inclusive(percentage, from.x, to.x),
-    // y: range_inclusive(percentage, from.y, to.y),
-    // }
-    // }
-    // }
+        for _ in 0..(steps as u32) {
+            let force = -lock.stiffness * (value - lock.target) - lock.damping * lock.velocity;
+            lock.velocity += (force / lock.mass) * step;
+            value += lock.velocity * step;
+        }
 
-    // #[inline]
-    // fn get_values(start: f64, end: f64) -> (f64, f64, f64) {
-    // let start = 3.0 * start;
-    // let end = 3.0 * end;
-    // let a = 1.0 - end + start;
-    // let b = end - (2.0 * start);
-    // (a, b, start)
-    // }
-    //
-    // fn interpolate(p: f64, start: f64, end: f64) -> f64 {
-    // let (a, b, c) = get_values(start, end);
-    // ((a * p + b) * p + c) * p
-    // }
-    //
-    // fn get_slope(p: f64, start: f64, end: f64) -> f64 {
-    // let (a, b, c) = get_values(start, end);
-    // (3.0 * a * p * p) + (2.0 * b * p) + c
-    // }
+        value
+    }
 
-    const EPSILON: f64 = 1e-6;
+    fn start_animating(&self, lock: &mut SpringAnimationState) {
+        if lock._animating.is_none() && !Self::is_resting(lock, self.inner.value.get()) {
+            let scale = if lock.essential {
+                1.0
+            } else {
+                reduced_motion_scale()
+            };
 
-    pub struct CubicBezier {
-        ax: f64,
+            if scale <= 0.0 {
+                lock.velocity = 0.0;
+                self.inner.value.set_neq(lock.target);
+                return;
+            }
+
+            lock.previous = 0.0;
+
+            let state = self.raw_clone();
+
+            lock._animating = Some(OnTimestampDiff::new(move |diff| {
+                let mut lock = state.inner.state.lock().unwrap_throw();
+
+                // Reduced motion makes the simulation run faster
+                let time = (((diff - lock.previous) / 1000.0) / scale).min(Self::MAX_FRAME);
+                lock.previous = diff;
+
+                if time > 0.0 {
+                    let value = Self::step(&mut lock, state.inner.value.get(), time);
+
+                    if Self::is_resting(&lock, value) {
+                        lock.velocity = 0.0;
+                        lock._animating = None;
+                        state.inner.value.set_neq(lock.target);
+                    } else {
+                        state.inner.value.set_neq(value);
+                    }
+                }
+            }));
+        }
+    }
+
+    /// Animates to `target`, keeping the current velocity.
+    pub fn animate_to(&self, target: f64) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+        lock.target = target;
+        self.start_animating(&mut lock);
+    }
+
+    /// See [`MutableAnimation::set_essential`].
+    pub fn set_essential(&self, essential: bool) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+        lock.essential = essential;
+    }
+
+    /// Immediately sets the value to `value` and stops animating.
+    pub fn jump_to(&self, value: f64) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+        lock.target = value;
+        lock.velocity = 0.0;
+        lock._animating = None;
+        self.inner.value.set_neq(value);
+    }
+
+    /// Sets the velocity (in units per second), for example to continue the
+    /// velocity of a drag gesture.
+    pub fn set_velocity(&self, velocity: f64) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+        lock.velocity = velocity;
+        self.start_animating(&mut lock);
+    }
+
+    pub fn set_config(&self, stiffness: f64, damping: f64, mass: f64) {
+        debug_assert!(stiffness > 0.0);
+        debug_assert!(damping >= 0.0);
+        debug_assert!(mass > 0.0);
+
+        let mut lock = self.inner.state.lock().unwrap_throw();
+        lock.stiffness = stiffness;
+        lock.damping = damping;
+        lock.mass = mass;
+    }
+
+    #[inline]
+    pub fn velocity(&self) -> f64 {
+        self.inner.state.lock().unwrap_throw().velocity
+    }
+
+    #[inline]
+    pub fn is_animating(&self) -> bool {
+        self.inner.state.lock().unwrap_throw()._animating.is_some()
+    }
+
+    #[inline]
+    pub fn current(&self) -> f64 {
+        self.inner.value.get()
+    }
+
+    #[inline]
+    pub fn signal(&self) -> SpringAnimationSignal {
+        SpringAnimationSignal(self.inner.value.signal())
+    }
+}
+
+/// A single track of a [`Timeline`].
+#[derive(Debug, Clone)]
+pub struct TimelineTrack {
+    animation: MutableAnimation,
+}
+
+impl TimelineTrack {
+    #[inline]
+    pub fn signal(&self) -> MutableAnimationSignal {
+        self.animation.signal()
+    }
+
+    #[inline]
+    pub fn current_percentage(&self) -> Percentage {
+        self.animation.current_percentage()
+    }
+}
+
+#[derive(Debug)]
+struct TimelineTrackState {
+    offset: f64,
+    duration: f64,
+    animation: MutableAnimation,
+}
+
+impl TimelineTrackState {
+    fn percentage(&self, position: f64) -> Percentage {
+        let percentage = if self.duration > 0.0 {
+            ((position - self.offset) / self.duration).clamp(0.0, 1.0)
+        } else if position >= self.offset {
+            1.0
+        } else {
+            0.0
+        };
+
+        Percentage::new_unchecked(percentage)
+    }
+}
+
+struct TimelineState {
+    tracks: Vec<TimelineTrackState>,
+    // The offset of the most recently added track, used by `with`
+    previous_offset: f64,
+    duration: f64,
+    position: f64,
+    playing: bool,
+    reversed: bool,
+    essential: bool,
+    _animating: Option<OnTimestampDiff>,
+}
+
+struct TimelineInner {
+    state: Mutex<TimelineState>,
+    value: Mutable<Percentage>,
+}
+
+/// Plays multiple animations in sequence, in parallel, or with staggered offsets.
+///
+/// Each track has its own [`Percentage`] signal, but they are all controlled
+/// by the timeline:
+///
+/// ```rust
+/// let timeline = Timeline::new();
+///
+/// // Runs from 0ms to 300ms
+/// let fade = timeline.then(300.0);
+/// // Runs from 300ms to 800ms
+/// let slide = timeline.then(500.0);
+/// // Runs from 300ms to 700ms
+/// let expand = timeline.with(400.0);
+/// // Each item runs for 200ms, starting at 800ms, 850ms, 900ms, etc.
+/// let items = timeline.stagger(5, 200.0, 50.0);
+///
+/// timeline.play();
+/// ```
+///
+/// All durations and offsets are in milliseconds.
+#[derive(Clone)]
+pub struct Timeline {
+    inner: Arc<TimelineInner>,
+}
+
+impl fmt::Debug for Timeline {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let state = self.inner.state.lock().unwrap_throw();
+
+        fmt.debug_struct("Timeline")
+            .field("tracks", &state.tracks)
+            .field("duration", &state.duration)
+            .field("position", &state.position)
+            .field("playing", &state.playing)
+            .field("reversed", &state.reversed)
+            .field("essential", &state.essential)
+            .finish()
+    }
+}
+
+impl Default for Timeline {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timeline {
+    /// Creates an empty timeline, which is paused at the start.
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(TimelineInner {
+                state: Mutex::new(TimelineState {
+                    tracks: vec![],
+                    previous_offset: 0.0,
+                    duration: 0.0,
+                    position: 0.0,
+                    playing: false,
+                    reversed: false,
+                    essential: false,
+                    _animating: None,
+                }),
+                value: Mutable::new(Percentage::new_unchecked(0.0)),
+            }),
+        }
+    }
+
+    #[inline]
+    fn raw_clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Adds a track which starts at `offset` and runs for `duration`.
+    pub fn at(&self, offset: f64, duration: f64) -> TimelineTrack {
+        debug_assert!(offset >= 0.0);
+        debug_assert!(duration >= 0.0);
+
+        let mut lock = self.inner.state.lock().unwrap_throw();
+
+        let track = TimelineTrackState {
+            offset,
+            duration,
+            animation: MutableAnimation::new(0.0),
+        };
+
+        track.animation.jump_to(track.percentage(lock.position));
+
+        let output = TimelineTrack {
+            animation: track.animation.clone(),
+        };
+
+        lock.tracks.push(track);
+        lock.previous_offset = offset;
+        lock.duration = lock.duration.max(offset + duration);
+
+        self.update_value(&lock);
+
+        output
+    }
+
+    /// Adds a track which starts after all of the existing tracks have finished.
+    pub fn then(&self, duration: f64) -> TimelineTrack {
+        let offset = self.inner.state.lock().unwrap_throw().duration;
+        self.at(offset, duration)
+    }
+
+    /// Adds a track which starts at the same time as the previously added track.
+    pub fn with(&self, duration: f64) -> TimelineTrack {
+        let offset = self.inner.state.lock().unwrap_throw().previous_offset;
+        self.at(offset, duration)
+    }
+
+    /// Adds `count` tracks which start after all of the existing tracks have finished.
+    ///
+    /// Each track starts `delay` after the previous track.
+    pub fn stagger(&self, count: usize, duration: f64, delay: f64) -> Vec<TimelineTrack> {
+        debug_assert!(delay >= 0.0);
+
+        let offset = self.inner.state.lock().unwrap_throw().duration;
+
+        let tracks = (0..count)
+            .map(|index| self.at(offset + (index as f64) * delay, duration))
+            .collect();
+
+        // This makes `with` run in parallel with the whole group
+        self.inner.state.lock().unwrap_throw().previous_offset = offset;
+
+        tracks
+    }
+
+    fn update_value(&self, lock: &TimelineState) {
+        let percentage = if lock.duration > 0.0 {
+            lock.position / lock.duration
+        } else {
+            0.0
+        };
+
+        self.inner
+            .value
+            .set_neq(Percentage::new_unchecked(percentage));
+    }
+
+    fn set_position(&self, lock: &mut TimelineState, position: f64) {
+        lock.position = position.clamp(0.0, lock.duration);
+
+        for track in lock.tracks.iter() {
+            track.animation.jump_to(track.percentage(lock.position));
+        }
+
+        self.update_value(lock);
+    }
+
+    fn is_finished(lock: &TimelineState) -> bool {
+        if lock.reversed {
+            lock.position <= 0.0
+        } else {
+            lock.position >= lock.duration
+        }
+    }
+
+    fn start_animating(&self, lock: &mut TimelineState) {
+        if lock.playing && !Self::is_finished(lock) {
+            let scale = if lock.essential {
+                1.0
+            } else {
+                reduced_motion_scale()
+            };
+
+            if scale <= 0.0 {
+                let position = if lock.reversed { 0.0 } else { lock.duration };
+                self.set_position(lock, position);
+                lock.playing = false;
+                lock._animating = None;
+                return;
+            }
+
+            let start = lock.position;
+            let reversed = lock.reversed;
+
+            let state = self.raw_clone();
+
+            lock._animating = Some(OnTimestampDiff::new(move |diff| {
+                let mut lock = state.inner.state.lock().unwrap_throw();
+
+                let diff = diff / scale;
+                let position = if reversed { start - diff } else { start + diff };
+
+                state.set_position(&mut lock, position);
+
+                if Self::is_finished(&lock) {
+                    lock.playing = false;
+                    lock._animating = None;
+                }
+            }));
+        } else {
+            lock.playing = false;
+            lock._animating = None;
+        }
+    }
+
+    /// See [`MutableAnimation::set_essential`].
+    pub fn set_essential(&self, essential: bool) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+        lock.essential = essential;
+    }
+
+    /// Plays the timeline from the current position.
+    ///
+    /// If the timeline has already finished then it plays again from the start.
+    pub fn play(&self) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+
+        if !lock.playing {
+            if Self::is_finished(&lock) {
+                let position = if lock.reversed { lock.duration } else { 0.0 };
+                self.set_position(&mut lock, position);
+            }
+
+            lock.playing = true;
+            self.start_animating(&mut lock);
+        }
+    }
+
+    pub fn pause(&self) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+
+        if lock.playing {
+            lock.playing = false;
+            lock._animating = None;
+        }
+    }
+
+    /// Jumps to `position` (in milliseconds), without changing whether it is playing or not.
+    pub fn seek(&self, position: f64) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+
+        self.set_position(&mut lock, position);
+
+        if lock.playing {
+            self.start_animating(&mut lock);
+        }
+    }
+
+    /// Changes the direction of the timeline.
+    ///
+    /// If the timeline is playing then it continues playing from the current
+    /// position in the new direction.
+    pub fn reverse(&self) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+
+        lock.reversed = !lock.reversed;
+
+        if lock.playing {
+            self.start_animating(&mut lock);
+        }
+    }
+
+    #[inline]
+    pub fn is_playing(&self) -> bool {
+        self.inner.state.lock().unwrap_throw().playing
+    }
+
+    #[inline]
+    pub fn is_reversed(&self) -> bool {
+        self.inner.state.lock().unwrap_throw().reversed
+    }
+
+    /// The total duration of all the tracks (in milliseconds).
+    #[inline]
+    pub fn duration(&self) -> f64 {
+        self.inner.state.lock().unwrap_throw().duration
+    }
+
+    /// The current position (in milliseconds).
+    #[inline]
+    pub fn position(&self) -> f64 {
+        self.inner.state.lock().unwrap_throw().position
+    }
+
+    /// The progress of the whole timeline.
+    #[inline]
+    pub fn signal(&self) -> MutableAnimationSignal {
+        MutableAnimationSignal(self.inner.value.signal())
+    }
+
+    #[inline]
+    pub fn current_percentage(&self) -> Percentage {
+        self.inner.value.get()
+    }
+}
+
+/// Values which can be animated with a [`Tween`].
+pub trait Interpolate: Clone {
+    /// Returns the value which is `percentage` of the way from `self` to `end`.
+    fn interpolate(&self, end: &Self, percentage: Percentage) -> Self;
+}
+
+impl Interpolate for f64 {
+    #[inline]
+    fn interpolate(&self, end: &Self, percentage: Percentage) -> Self {
+        percentage.range_inclusive(*self, *end)
+    }
+}
+
+impl Interpolate for f32 {
+    #[inline]
+    fn interpolate(&self, end: &Self, percentage: Percentage) -> Self {
+        percentage.range_inclusive(*self as f64, *end as f64) as f32
+    }
+}
+
+macro_rules! interpolate_tuple {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name),+> Interpolate for ($($name,)+) where $($name: Interpolate),+ {
+            #[inline]
+            fn interpolate(&self, end: &Self, percentage: Percentage) -> Self {
+                ($(self.$index.interpolate(&end.$index, percentage),)+)
+            }
+        }
+    };
+}
+
+interpolate_tuple!(A 0);
+interpolate_tuple!(A 0, B 1);
+interpolate_tuple!(A 0, B 1, C 2);
+interpolate_tuple!(A 0, B 1, C 2, D 3);
+
+impl<A, const N: usize> Interpolate for [A; N]
+where
+    A: Interpolate,
+{
+    #[inline]
+    fn interpolate(&self, end: &Self, percentage: Percentage) -> Self {
+        std::array::from_fn(|index| self[index].interpolate(&end[index], percentage))
+    }
+}
+
+/// An RGBA color which can be animated with a [`Tween`].
+///
+/// The `r`, `g` and `b` channels are from `0.0` to `255.0`, and `a` is from `0.0` to `1.0`.
+///
+/// It displays as a CSS `rgba(...)` color.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgba {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64,
+}
+
+impl Rgba {
+    #[inline]
+    pub fn new(r: f64, g: f64, b: f64, a: f64) -> Self {
+        Self { r, g, b, a }
+    }
+}
+
+impl Interpolate for Rgba {
+    #[inline]
+    fn interpolate(&self, end: &Self, percentage: Percentage) -> Self {
+        Self {
+            r: self.r.interpolate(&end.r, percentage),
+            g: self.g.interpolate(&end.g, percentage),
+            b: self.b.interpolate(&end.b, percentage),
+            a: self.a.interpolate(&end.a, percentage),
+        }
+    }
+}
+
+impl fmt::Display for Rgba {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "rgba({}, {}, {}, {})",
+            self.r.round(),
+            self.g.round(),
+            self.b.round(),
+            self.a
+        )
+    }
+}
+
+/// A 2D transform which can be animated with a [`Tween`].
+///
+/// The translation is in `px` and the rotation is in degrees.
+///
+/// It displays as a CSS `transform` value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform2d {
+    pub translate_x: f64,
+    pub translate_y: f64,
+    pub scale_x: f64,
+    pub scale_y: f64,
+    pub rotate: f64,
+}
+
+impl Default for Transform2d {
+    /// The identity transform.
+    #[inline]
+    fn default() -> Self {
+        Self {
+            translate_x: 0.0,
+            translate_y: 0.0,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            rotate: 0.0,
+        }
+    }
+}
+
+impl Interpolate for Transform2d {
+    #[inline]
+    fn interpolate(&self, end: &Self, percentage: Percentage) -> Self {
+        Self {
+            translate_x: self.translate_x.interpolate(&end.translate_x, percentage),
+            translate_y: self.translate_y.interpolate(&end.translate_y, percentage),
+            scale_x: self.scale_x.interpolate(&end.scale_x, percentage),
+            scale_y: self.scale_y.interpolate(&end.scale_y, percentage),
+            rotate: self.rotate.interpolate(&end.rotate, percentage),
+        }
+    }
+}
+
+impl fmt::Display for Transform2d {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "translate({}px, {}px) rotate({}deg) scale({}, {})",
+            self.translate_x, self.translate_y, self.rotate, self.scale_x, self.scale_y
+        )
+    }
+}
+
+/// Animates any value which implements [`Interpolate`].
+///
+/// It has the same play / pause / duration behavior as [`MutableAnimation`]:
+///
+/// ```rust
+/// let color = Tween::new(300.0, Rgba::new(255.0, 0.0, 0.0, 1.0));
+///
+/// color.animate_to(Rgba::new(0.0, 0.0, 255.0, 1.0));
+///
+/// html!("div", {
+///     .style_signal("color", color.signal().map(|x| x.to_string()))
+/// })
+/// ```
+#[derive(Debug)]
+pub struct Tween<T> {
+    animation: MutableAnimation,
+    // The start and end values of the current animation
+    values: Mutable<(T, T)>,
+    easing: Mutable<easing::Easing>,
+}
+
+impl<T> Clone for Tween<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            animation: self.animation.clone(),
+            values: self.values.clone(),
+            easing: self.easing.clone(),
+        }
+    }
+}
+
+impl<T> Tween<T>
+where
+    T: Interpolate,
+{
+    #[inline]
+    pub fn new(duration: f64, initial: T) -> Self {
+        Self {
+            animation: MutableAnimation::new_with_initial(duration, Percentage::END),
+            values: Mutable::new((initial.clone(), initial)),
+            easing: Mutable::new(easing::Easing::Linear),
+        }
+    }
+
+    /// Animates from the current value to `end`.
+    ///
+    /// If it is already animating then it starts from the current value, so
+    /// it always takes `duration` to reach `end`.
+    pub fn animate_to(&self, end: T) {
+        let start = self.current();
+        self.values.set((start, end));
+        self.animation.jump_to(Percentage::START);
+        self.animation.animate_to(Percentage::END);
+    }
+
+    /// Immediately changes the value to `end` without animating.
+    pub fn jump_to(&self, end: T) {
+        self.values.set((end.clone(), end));
+        self.animation.jump_to(Percentage::END);
+    }
+
+    #[inline]
+    pub fn set_duration(&self, duration: f64) {
+        self.animation.set_duration(duration);
+    }
+
+    /// Changes the easing, the default is [`Easing::Linear`](easing::Easing::Linear).
+    #[inline]
+    pub fn set_easing(&self, easing: easing::Easing) {
+        self.easing.set_neq(easing);
+    }
+
+    /// See [`MutableAnimation::set_essential`].
+    #[inline]
+    pub fn set_essential(&self, essential: bool) {
+        self.animation.set_essential(essential);
+    }
+
+    #[inline]
+    pub fn pause(&self) {
+        self.animation.pause();
+    }
+
+    #[inline]
+    pub fn play(&self) {
+        self.animation.play();
+    }
+
+    pub fn current(&self) -> T {
+        let values = self.values.lock_ref();
+        let percentage = self
+            .easing
+            .get()
+            .easing(self.animation.current_percentage());
+        values.0.interpolate(&values.1, percentage)
+    }
+
+    /// Returns the value which it is animating towards.
+    #[inline]
+    pub fn end(&self) -> T {
+        self.values.lock_ref().1.clone()
+    }
+
+    pub fn signal(&self) -> impl Signal<Item = T> {
+        futures_signals::map_ref! {
+            let percentage = self.animation.signal(),
+            let values = self.values.signal_cloned(),
+            let easing = self.easing.signal() =>
+            values.0.interpolate(&values.1, easing.easing(*percentage))
+        }
+    }
+}
+
+/// A keyframe for [`WebKeyframes`].
+#[derive(Debug, Clone)]
+pub struct WebKeyframe {
+    object: Object,
+}
+
+// Converts a CSS property name into the name which is used by the Web Animations API
+fn keyframe_property(name: &str) -> Cow<'_, str> {
+    match name {
+        "float" => Cow::Borrowed("cssFloat"),
+        "offset" => Cow::Borrowed("cssOffset"),
+        _ if name.starts_with("--") || !name.contains('-') => Cow::Borrowed(name),
+        _ => {
+            let mut output = String::with_capacity(name.len());
+            let mut upper = false;
+
+            for c in name.chars() {
+                if c == '-' {
+                    upper = true;
+                } else if upper {
+                    upper = false;
+                    output.push(c.to_ascii_uppercase());
+                } else {
+                    output.push(c);
+                }
+            }
+
+            Cow::Owned(output)
+        }
+    }
+}
+
+impl WebKeyframe {
+    /// Sets a CSS style, the `name` is the same as in [`DomBuilder::style`](crate::DomBuilder::style).
+    #[inline]
+    pub fn style(self, name: &str, value: &str) -> Self {
+        bindings::set_property(
+            &self.object,
+            &keyframe_property(name),
+            &JsValue::from(value),
+        );
+        self
+    }
+
+    /// Sets the position of the keyframe, from `0.0` to `1.0`.
+    ///
+    /// If it isn't set then the keyframes are evenly spaced.
+    #[inline]
+    pub fn offset(self, offset: f64) -> Self {
+        bindings::set_property(&self.object, "offset", &JsValue::from(offset));
+        self
+    }
+
+    /// Sets the CSS easing function which is used until the next keyframe.
+    #[inline]
+    pub fn easing(self, easing: &str) -> Self {
+        bindings::set_property(&self.object, "easing", &JsValue::from(easing));
+        self
+    }
+}
+
+/// Keyframes for [`DomBuilder::web_animation`](crate::DomBuilder::web_animation).
+#[derive(Debug, Clone)]
+pub struct WebKeyframes {
+    keyframes: Array,
+}
+
+impl Default for WebKeyframes {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebKeyframes {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            keyframes: Array::new(),
+        }
+    }
+
+    #[inline]
+    pub fn keyframe<F>(self, f: F) -> Self
+    where
+        F: FnOnce(WebKeyframe) -> WebKeyframe,
+    {
+        let keyframe = f(WebKeyframe {
+            object: Object::new(),
+        });
+        self.keyframes.push(&keyframe.object);
+        self
+    }
+}
+
+/// Timing options for [`DomBuilder::web_animation`](crate::DomBuilder::web_animation).
+///
+/// The durations are in milliseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebAnimationOptions {
+    pub duration: f64,
+    pub delay: f64,
+    pub end_delay: f64,
+    /// The CSS [easing function](https://developer.mozilla.org/en-US/docs/Web/CSS/easing-function).
+    pub easing: String,
+    /// This can be [`f64::INFINITY`] to repeat forever.
+    pub iterations: f64,
+    pub direction: PlaybackDirection,
+    pub fill: FillMode,
+    /// See [`MutableAnimation::set_essential`].
+    pub essential: bool,
+}
+
+impl Default for WebAnimationOptions {
+    fn default() -> Self {
+        Self {
+            duration: 0.0,
+            delay: 0.0,
+            end_delay: 0.0,
+            easing: "linear".to_owned(),
+            iterations: 1.0,
+            direction: PlaybackDirection::Normal,
+            fill: FillMode::Auto,
+            essential: false,
+        }
+    }
+}
+
+impl WebAnimationOptions {
+    fn to_js(&self) -> JsValue {
+        let duration = if self.essential {
+            self.duration
+        } else {
+            self.duration * reduced_motion_scale()
+        };
+
+        let options = Object::new();
+        bindings::set_property(&options, "duration", &JsValue::from(duration));
+        bindings::set_property(&options, "delay", &JsValue::from(self.delay));
+        bindings::set_property(&options, "endDelay", &JsValue::from(self.end_delay));
+        bindings::set_property(&options, "easing", &JsValue::from(&self.easing));
+        bindings::set_property(&options, "iterations", &JsValue::from(self.iterations));
+        bindings::set_property(&options, "direction", &JsValue::from(self.direction));
+        bindings::set_property(&options, "fill", &JsValue::from(self.fill));
+        options.into()
+    }
+}
+
+/// An animation which is running in the browser with the [Web Animations API](https://developer.mozilla.org/en-US/docs/Web/API/Web_Animations_API).
+///
+/// This is created with [`DomBuilder::web_animation`](crate::DomBuilder::web_animation).
+#[derive(Debug, Clone)]
+pub struct WebAnimation {
+    animation: Animation,
+}
+
+impl WebAnimation {
+    // This returns an error if the browser rejects the keyframes or the options
+    pub(crate) fn new(
+        element: &Element,
+        keyframes: &WebKeyframes,
+        options: &WebAnimationOptions,
+    ) -> Result<Self, JsValue> {
+        let animation = bindings::animate(element, &keyframes.keyframes, &options.to_js())?;
+        Ok(Self { animation })
+    }
+
+    /// Returns the underlying [`Animation`].
+    #[inline]
+    pub fn raw(&self) -> &Animation {
+        &self.animation
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn play(&self) {
+        self.animation.play().unwrap_js();
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn pause(&self) {
+        self.animation.pause().unwrap_js();
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn reverse(&self) {
+        self.animation.reverse().unwrap_js();
+    }
+
+    /// Jumps to the end of the animation.
+    #[inline]
+    #[track_caller]
+    pub fn finish(&self) {
+        self.animation.finish().unwrap_js();
+    }
+
+    /// Stops the animation and removes its effects.
+    #[inline]
+    pub fn cancel(&self) {
+        self.animation.cancel();
+    }
+
+    #[inline]
+    pub fn set_playback_rate(&self, rate: f64) {
+        self.animation.set_playback_rate(rate);
+    }
+
+    /// Jumps to `time` (in milliseconds).
+    #[inline]
+    pub fn set_current_time(&self, time: f64) {
+        self.animation.set_current_time(Some(time));
+    }
+
+    /// The current time (in milliseconds), it is `None` if the animation is not running.
+    #[inline]
+    pub fn current_time(&self) -> Option<f64> {
+        self.animation.current_time()
+    }
+
+    /// Returns a `Signal` of the [current time](WebAnimation::current_time), which is updated every frame.
+    ///
+    /// The `Signal` ends after the animation [finishes](WebAnimation::finished) or is cancelled.
+    #[track_caller]
+    pub fn current_time_signal(&self) -> impl Signal<Item = Option<f64>> {
+        CurrentTimeSignal {
+            animation: self.animation.clone(),
+            finished: Some(JsFuture::from(self.animation.finished().unwrap_js())),
+            timestamps: Some(timestamps()),
+            previous: None,
+        }
+    }
+
+    /// Returns a `Future` which resolves when the animation finishes.
+    ///
+    /// If the animation is cancelled then it returns an `Err`.
+    #[track_caller]
+    pub fn finished(&self) -> impl Future<Output = Result<(), JsValue>> {
+        let finished = JsFuture::from(self.animation.finished().unwrap_js());
+        async move { finished.await.map(|_| ()) }
+    }
+}
+
+// This stops listening to the timestamps when the animation finishes, so that the frame loop can stop
+struct CurrentTimeSignal {
+    animation: Animation,
+    finished: Option<JsFuture>,
+    timestamps: Option<Timestamps>,
+    previous: Option<Option<f64>>,
+}
+
+impl Signal for CurrentTimeSignal {
+    type Item = Option<f64>;
+
+    fn poll_change(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            // It stops after it has been rejected, because a cancelled animation doesn't change
+            if let Some(finished) = &mut this.finished {
+                if finished.poll_unpin(cx).is_ready() {
+                    this.finished = None;
+                    this.timestamps = None;
+                }
+            }
+
+            let is_done = match &mut this.timestamps {
+                Some(timestamps) => match timestamps.poll_change_unpin(cx) {
+                    Poll::Ready(Some(_)) => false,
+                    Poll::Ready(None) => {
+                        this.timestamps = None;
+                        true
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+                None => true,
+            };
+
+            let current_time = this.animation.current_time();
+
+            if this.previous != Some(current_time) {
+                this.previous = Some(current_time);
+                return Poll::Ready(Some(current_time));
+            } else if is_done {
+                return Poll::Ready(None);
+            }
+        }
+    }
+}
+
+pub mod easing {
+    use std::{cell::Cell, f64::consts::PI, fmt, str::FromStr};
+
+    use once_cell::sync::Lazy;
+
+    use super::Percentage;
+
+    // TODO should this use map rather than map_unchecked ?
+    #[inline]
+    pub fn powi(p: Percentage, n: i32) -> Percentage {
+        p.map_unchecked(|p| p.powi(n))
+    }
+
+    #[inline]
+    pub fn cubic(p: Percentage) -> Percentage {
+        powi(p, 3)
+    }
+
+    #[inline]
+    pub fn out<F>(p: Percentage, f: F) -> Percentage
+    where
+        F: FnOnce(Percentage) -> Percentage,
+    {
+        f(p.invert()).invert()
+    }
+
+    pub fn in_out<F>(p: Percentage, f: F) -> Percentage
+    where
+        F: FnOnce(Percentage) -> Percentage,
+    {
+        p.map_unchecked(|p| {
+            if p <= 0.5 {
+                f(Percentage::new_unchecked(p * 2.0)).into_f64() / 2.0
+            } else {
+                1.0 - (f(Percentage::new_unchecked((1.0 - p) * 2.0)).into_f64() / 2.0)
+            }
+        })
+    }
+
+    // pub struct Point {
+    // pub x: f64,
+    // pub y: f64,
+    // }
+
+    // impl Point {
+    // fn range_inclusive(percentage: f64, from: &Self, to: &Self) -> Self {
+    // Point {
+    // x: range_inclusive(percentage, from.x, to.x),
+    // y: range_inclusive(percentage, from.y, to.y),
+    // }
+    // }
+    // }
+
+    // #[inline]
+    // fn get_values(start: f64, end: f64) -> (f64, f64, f64) {
+    // let start = 3.0 * start;
+    // let end = 3.0 * end;
+    // let a = 1.0 - end + start;
+    // let b = end - (2.0 * start);
+    // (a, b, start)
+    // }
+    //
+    // fn interpolate(p: f64, start: f64, end: f64) -> f64 {
+    // let (a, b, c) = get_values(start, end);
+    // ((a * p + b) * p + c) * p
+    // }
+    //
+    // fn get_slope(p: f64, start: f64, end: f64) -> f64 {
+    // let (a, b, c) = get_values(start, end);
+    // (3.0 * a * p * p) + (2.0 * b * p) + c
+    // }
+
+    const EPSILON: f64 = 1e-6;
+
+    #[derive(Debug, Clone, Copy)]
+    pub struct CubicBezier {
+        ax: f64,
         bx: f64,
         cx: f64,
 
@@ -928,10 +2538,11 @@ pub mod easing {
 
     impl CubicBezier {
         pub fn new(x1: f64, y1: f64, x2: f64, y2: f64) -> Self {
+            // The y values can be outside of the range, which makes it overshoot (the same as CSS)
             assert!(x1 >= 0.0 && x1 <= 1.0);
-            assert!(y1 >= 0.0 && y1 <= 1.0);
+            assert!(y1.is_finite());
             assert!(x2 >= 0.0 && x2 <= 1.0);
-            assert!(y2 >= 0.0 && y2 <= 1.0);
+            assert!(y2.is_finite());
 
             let cx = 3.0 * x1;
             let bx = 3.0 * (x2 - x1) - cx;
@@ -1128,6 +2739,426 @@ pub mod easing {
         // }
         // }
     }
+
+    #[inline]
+    pub fn sine(p: Percentage) -> Percentage {
+        p.map_unchecked(|p| 1.0 - (p * PI / 2.0).cos())
+    }
+
+    #[inline]
+    pub fn expo(p: Percentage) -> Percentage {
+        p.map_unchecked(|p| {
+            if p == 0.0 {
+                0.0
+            } else {
+                2.0_f64.powf(10.0 * p - 10.0)
+            }
+        })
+    }
+
+    #[inline]
+    pub fn circ(p: Percentage) -> Percentage {
+        p.map_unchecked(|p| 1.0 - (1.0 - p * p).sqrt())
+    }
+
+    /// Goes slightly below `0.0` before going to `1.0`.
+    #[inline]
+    pub fn back(p: Percentage) -> Percentage {
+        const C1: f64 = 1.70158;
+        const C3: f64 = C1 + 1.0;
+
+        p.map_unchecked(|p| C3 * p * p * p - C1 * p * p)
+    }
+
+    /// Oscillates around `0.0` before going to `1.0`.
+    #[inline]
+    pub fn elastic(p: Percentage) -> Percentage {
+        const C4: f64 = (2.0 * PI) / 3.0;
+
+        p.map_unchecked(|p| {
+            if p == 0.0 || p == 1.0 {
+                p
+            } else {
+                -(2.0_f64.powf(10.0 * p - 10.0)) * ((p * 10.0 - 10.75) * C4).sin()
+            }
+        })
+    }
+
+    #[inline]
+    pub fn bounce(p: Percentage) -> Percentage {
+        fn bounce_out(p: f64) -> f64 {
+            const N1: f64 = 7.5625;
+            const D1: f64 = 2.75;
+
+            if p < 1.0 / D1 {
+                N1 * p * p
+            } else if p < 2.0 / D1 {
+                let p = p - 1.5 / D1;
+                N1 * p * p + 0.75
+            } else if p < 2.5 / D1 {
+                let p = p - 2.25 / D1;
+                N1 * p * p + 0.9375
+            } else {
+                let p = p - 2.625 / D1;
+                N1 * p * p + 0.984375
+            }
+        }
+
+        p.map_unchecked(|p| 1.0 - bounce_out(1.0 - p))
+    }
+
+    /// When the jumps happen for [`steps`].
+    ///
+    /// This is the same as the CSS [`<step-position>`](https://developer.mozilla.org/en-US/docs/Web/CSS/easing-function/steps#step-position).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum StepPosition {
+        JumpStart,
+        JumpEnd,
+        JumpNone,
+        JumpBoth,
+    }
+
+    impl StepPosition {
+        fn as_str(self) -> &'static str {
+            match self {
+                StepPosition::JumpStart => "jump-start",
+                StepPosition::JumpEnd => "jump-end",
+                StepPosition::JumpNone => "jump-none",
+                StepPosition::JumpBoth => "jump-both",
+            }
+        }
+    }
+
+    /// Divides the animation into `steps` equal intervals, the same as the CSS `steps()` function.
+    pub fn steps(p: Percentage, steps: u32, position: StepPosition) -> Percentage {
+        debug_assert!(steps > 0);
+        debug_assert!(steps > 1 || position != StepPosition::JumpNone);
+
+        p.map_unchecked(|p| {
+            let steps = steps as f64;
+
+            let mut step = (p * steps).floor();
+
+            if let StepPosition::JumpStart | StepPosition::JumpBoth = position {
+                step += 1.0;
+            }
+
+            let jumps = match position {
+                StepPosition::JumpStart | StepPosition::JumpEnd => steps,
+                StepPosition::JumpNone => steps - 1.0,
+                StepPosition::JumpBoth => steps + 1.0,
+            };
+
+            step.clamp(0.0, jumps) / jumps
+        })
+    }
+
+    // The curves are only created once, instead of on every frame
+    static EASE: Lazy<CubicBezier> = Lazy::new(|| CubicBezier::new(0.25, 0.1, 0.25, 1.0));
+    static EASE_IN: Lazy<CubicBezier> = Lazy::new(|| CubicBezier::new(0.42, 0.0, 1.0, 1.0));
+    static EASE_OUT: Lazy<CubicBezier> = Lazy::new(|| CubicBezier::new(0.0, 0.0, 0.58, 1.0));
+    static EASE_IN_OUT: Lazy<CubicBezier> = Lazy::new(|| CubicBezier::new(0.42, 0.0, 0.58, 1.0));
+
+    /// The same as the CSS `ease` timing function.
+    #[inline]
+    pub fn ease(p: Percentage) -> Percentage {
+        EASE.easing(p)
+    }
+
+    /// The same as the CSS `ease-in` timing function.
+    #[inline]
+    pub fn ease_in(p: Percentage) -> Percentage {
+        EASE_IN.easing(p)
+    }
+
+    /// The same as the CSS `ease-out` timing function.
+    #[inline]
+    pub fn ease_out(p: Percentage) -> Percentage {
+        EASE_OUT.easing(p)
+    }
+
+    /// The same as the CSS `ease-in-out` timing function.
+    #[inline]
+    pub fn ease_in_out(p: Percentage) -> Percentage {
+        EASE_IN_OUT.easing(p)
+    }
+
+    /// The shape of an [`Easing::In`], [`Easing::Out`], or [`Easing::InOut`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Curve {
+        Quad,
+        Cubic,
+        Quart,
+        Quint,
+        Sine,
+        Expo,
+        Circ,
+        Back,
+        Elastic,
+        Bounce,
+    }
+
+    impl Curve {
+        const ALL: [Curve; 10] = [
+            Curve::Quad,
+            Curve::Cubic,
+            Curve::Quart,
+            Curve::Quint,
+            Curve::Sine,
+            Curve::Expo,
+            Curve::Circ,
+            Curve::Back,
+            Curve::Elastic,
+            Curve::Bounce,
+        ];
+
+        fn as_str(self) -> &'static str {
+            match self {
+                Curve::Quad => "quad",
+                Curve::Cubic => "cubic",
+                Curve::Quart => "quart",
+                Curve::Quint => "quint",
+                Curve::Sine => "sine",
+                Curve::Expo => "expo",
+                Curve::Circ => "circ",
+                Curve::Back => "back",
+                Curve::Elastic => "elastic",
+                Curve::Bounce => "bounce",
+            }
+        }
+
+        #[inline]
+        pub fn easing(self, p: Percentage) -> Percentage {
+            match self {
+                Curve::Quad => powi(p, 2),
+                Curve::Cubic => cubic(p),
+                Curve::Quart => powi(p, 4),
+                Curve::Quint => powi(p, 5),
+                Curve::Sine => sine(p),
+                Curve::Expo => expo(p),
+                Curve::Circ => circ(p),
+                Curve::Back => back(p),
+                Curve::Elastic => elastic(p),
+                Curve::Bounce => bounce(p),
+            }
+        }
+    }
+
+    /// An easing function which can be stored, compared, and converted to / from a string.
+    ///
+    /// It can be parsed from a CSS [`<easing-function>`](https://developer.mozilla.org/en-US/docs/Web/CSS/easing-function):
+    ///
+    /// ```rust
+    /// let easing: Easing = "cubic-bezier(0.25, 0.1, 0.25, 1)".parse().unwrap();
+    ///
+    /// let value = easing.easing(animation.current_percentage());
+    /// ```
+    ///
+    /// The [`Curve`] easings don't exist in CSS, so they use the names from
+    /// [easings.net](https://easings.net/) (such as `"ease-in-out-sine"`).
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Easing {
+        Linear,
+        Ease,
+        EaseIn,
+        EaseOut,
+        EaseInOut,
+        /// The x values are clamped between `0.0` and `1.0`, and if any value
+        /// isn't finite then it is the same as [`Easing::Linear`].
+        CubicBezier(f64, f64, f64, f64),
+        /// `0` steps is the same as `1` step, and `1` step with
+        /// [`StepPosition::JumpNone`] is the same as [`StepPosition::JumpEnd`].
+        Steps(u32, StepPosition),
+        In(Curve),
+        Out(Curve),
+        InOut(Curve),
+    }
+
+    impl Default for Easing {
+        #[inline]
+        fn default() -> Self {
+            Easing::Linear
+        }
+    }
+
+    // Returns the curve for the points, the most recently used curve is cached so
+    // that it isn't created on every frame
+    fn cubic_bezier(points: [f64; 4]) -> CubicBezier {
+        thread_local! {
+            static CACHE: Cell<Option<([f64; 4], CubicBezier)>> = const { Cell::new(None) };
+        }
+
+        CACHE.with(|cache| match cache.get() {
+            Some((cached, curve)) if cached == points => curve,
+            _ => {
+                let curve = CubicBezier::new(points[0], points[1], points[2], points[3]);
+                cache.set(Some((points, curve)));
+                curve
+            }
+        })
+    }
+
+    impl Easing {
+        pub fn easing(&self, p: Percentage) -> Percentage {
+            match *self {
+                Easing::Linear => p,
+                Easing::Ease => ease(p),
+                Easing::EaseIn => ease_in(p),
+                Easing::EaseOut => ease_out(p),
+                Easing::EaseInOut => ease_in_out(p),
+                Easing::CubicBezier(x1, y1, x2, y2) => {
+                    // The fields are public, so they are normalised instead of panicking
+                    if [x1, y1, x2, y2].iter().all(|x| x.is_finite()) {
+                        cubic_bezier([x1.clamp(0.0, 1.0), y1, x2.clamp(0.0, 1.0), y2]).easing(p)
+                    } else {
+                        p
+                    }
+                }
+                Easing::Steps(n, position) => {
+                    let n = n.max(1);
+
+                    let position = if n == 1 && position == StepPosition::JumpNone {
+                        StepPosition::JumpEnd
+                    } else {
+                        position
+                    };
+
+                    steps(p, n, position)
+                }
+                Easing::In(curve) => curve.easing(p),
+                Easing::Out(curve) => out(p, |p| curve.easing(p)),
+                Easing::InOut(curve) => in_out(p, |p| curve.easing(p)),
+            }
+        }
+
+        /// Returns `false` for the [`Curve`] easings, because they can't be used in CSS.
+        pub fn is_css(&self) -> bool {
+            !matches!(self, Easing::In(_) | Easing::Out(_) | Easing::InOut(_))
+        }
+    }
+
+    impl fmt::Display for Easing {
+        fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Easing::Linear => fmt.write_str("linear"),
+                Easing::Ease => fmt.write_str("ease"),
+                Easing::EaseIn => fmt.write_str("ease-in"),
+                Easing::EaseOut => fmt.write_str("ease-out"),
+                Easing::EaseInOut => fmt.write_str("ease-in-out"),
+                Easing::CubicBezier(x1, y1, x2, y2) => {
+                    write!(fmt, "cubic-bezier({}, {}, {}, {})", x1, y1, x2, y2)
+                }
+                Easing::Steps(n, position) => write!(fmt, "steps({}, {})", n, position.as_str()),
+                Easing::In(curve) => write!(fmt, "ease-in-{}", curve.as_str()),
+                Easing::Out(curve) => write!(fmt, "ease-out-{}", curve.as_str()),
+                Easing::InOut(curve) => write!(fmt, "ease-in-out-{}", curve.as_str()),
+            }
+        }
+    }
+
+    /// The error which is returned when parsing an [`Easing`] fails.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ParseEasingError(String);
+
+    impl fmt::Display for ParseEasingError {
+        fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmt, "invalid easing function: {}", self.0)
+        }
+    }
+
+    impl std::error::Error for ParseEasingError {}
+
+    // Parses the arguments of a CSS function such as `steps(4, jump-end)`
+    fn function<'a>(input: &'a str, name: &str) -> Option<Vec<&'a str>> {
+        let args = input.strip_prefix(name)?.trim_start();
+        let args = args.strip_prefix('(')?.strip_suffix(')')?;
+        Some(args.split(',').map(|arg| arg.trim()).collect())
+    }
+
+    fn parse(input: &str) -> Option<Easing> {
+        match input {
+            "linear" => return Some(Easing::Linear),
+            "ease" => return Some(Easing::Ease),
+            "ease-in" => return Some(Easing::EaseIn),
+            "ease-out" => return Some(Easing::EaseOut),
+            "ease-in-out" => return Some(Easing::EaseInOut),
+            "step-start" => return Some(Easing::Steps(1, StepPosition::JumpStart)),
+            "step-end" => return Some(Easing::Steps(1, StepPosition::JumpEnd)),
+            _ => {}
+        }
+
+        if let Some(args) = function(input, "cubic-bezier") {
+            if let [x1, y1, x2, y2] = args[..] {
+                let x1 = x1.parse().ok()?;
+                let y1: f64 = y1.parse().ok()?;
+                let x2 = x2.parse().ok()?;
+                let y2: f64 = y2.parse().ok()?;
+
+                // Rust accepts "NaN" and "inf", but CSS doesn't
+                if !y1.is_finite() || !y2.is_finite() {
+                    return None;
+                }
+
+                if !(0.0..=1.0).contains(&x1) || !(0.0..=1.0).contains(&x2) {
+                    return None;
+                }
+
+                return Some(Easing::CubicBezier(x1, y1, x2, y2));
+            }
+
+            return None;
+        }
+
+        if let Some(args) = function(input, "steps") {
+            let (n, position) = match args[..] {
+                [n] => (n, StepPosition::JumpEnd),
+                [n, position] => (
+                    n,
+                    match position {
+                        "jump-start" | "start" => StepPosition::JumpStart,
+                        "jump-end" | "end" => StepPosition::JumpEnd,
+                        "jump-none" => StepPosition::JumpNone,
+                        "jump-both" => StepPosition::JumpBoth,
+                        _ => return None,
+                    },
+                ),
+                _ => return None,
+            };
+
+            let n: u32 = n.parse().ok()?;
+
+            if n == 0 || (n == 1 && position == StepPosition::JumpNone) {
+                return None;
+            }
+
+            return Some(Easing::Steps(n, position));
+        }
+
+        // The order matters because "ease-in-" is a prefix of "ease-in-out-"
+        let (kind, name): (fn(Curve) -> Easing, &str) =
+            if let Some(name) = input.strip_prefix("ease-in-out-") {
+                (Easing::InOut, name)
+            } else if let Some(name) = input.strip_prefix("ease-in-") {
+                (Easing::In, name)
+            } else if let Some(name) = input.strip_prefix("ease-out-") {
+                (Easing::Out, name)
+            } else {
+                return None;
+            };
+
+        Curve::ALL
+            .iter()
+            .find(|curve| curve.as_str() == name)
+            .map(|curve| kind(*curve))
+    }
+
+    impl FromStr for Easing {
+        type Err = ParseEasingError;
+
+        fn from_str(input: &str) -> Result<Self, Self::Err> {
+            parse(input.trim()).ok_or_else(|| ParseEasingError(input.to_owned()))
+        }
+    }
 }
 
 // cubic_bezier(t,
@@ -1136,3 +3167,280 @@ pub mod easing {
 // Percentage::new(0.66),
 // Percentage::new(0.66),
 // )
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use futures_signals::signal::Signal;
+    use futures_util::task::noop_waker_ref;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::{set_clock, set_time_scale, timestamps, ManualClock};
+
+    #[test]
+    fn manual_clock() {
+        let clock = ManualClock::new();
+        set_clock(clock.clone());
+
+        let mut timestamps = timestamps();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut poll = || Pin::new(&mut timestamps).poll_change(&mut cx);
+
+        assert_eq!(poll(), Poll::Ready(Some(None)));
+        assert_eq!(poll(), Poll::Pending);
+
+        clock.advance(16.0);
+        assert_eq!(poll(), Poll::Ready(Some(Some(16.0))));
+
+        set_time_scale(0.5);
+        clock.advance(16.0);
+        assert_eq!(poll(), Poll::Ready(Some(Some(24.0))));
+
+        set_time_scale(0.0);
+        clock.advance(16.0);
+        assert_eq!(poll(), Poll::Ready(Some(Some(24.0))));
+        assert_eq!(clock.now(), 48.0);
+    }
+
+    #[test]
+    fn manual_clock_animation() {
+        use super::{set_reduced_motion, MutableAnimation, Percentage, ReducedMotion};
+
+        set_reduced_motion(ReducedMotion::NoPreference);
+
+        let clock = ManualClock::new();
+        set_clock(clock.clone());
+
+        let animation = MutableAnimation::new(1000.0);
+        animation.animate_to(Percentage::END);
+
+        clock.advance(0.0);
+        assert_eq!(animation.current_percentage(), Percentage::START);
+
+        clock.advance(500.0);
+        assert_eq!(animation.current_percentage(), Percentage::new(0.5));
+
+        clock.advance(500.0);
+        assert_eq!(animation.current_percentage(), Percentage::END);
+    }
+
+    #[test]
+    fn reduced_motion_spring() {
+        use super::{set_reduced_motion, set_reduced_motion_scale, ReducedMotion, SpringAnimation};
+
+        set_reduced_motion(ReducedMotion::Reduce);
+
+        let clock = ManualClock::new();
+        set_clock(clock.clone());
+
+        let spring = SpringAnimation::new(0.0);
+        spring.animate_to(100.0);
+
+        assert_eq!(spring.current(), 100.0);
+        assert!(!spring.is_animating());
+
+        let essential = SpringAnimation::new(0.0);
+        essential.set_essential(true);
+        essential.animate_to(100.0);
+
+        assert_eq!(essential.current(), 0.0);
+        assert!(essential.is_animating());
+
+        set_reduced_motion_scale(0.5);
+
+        let normal = SpringAnimation::new(0.0);
+        set_reduced_motion(ReducedMotion::NoPreference);
+        normal.animate_to(100.0);
+
+        set_reduced_motion(ReducedMotion::Reduce);
+        let reduced = SpringAnimation::new(0.0);
+        reduced.animate_to(100.0);
+
+        clock.advance(0.0);
+        clock.advance(16.0);
+
+        assert!(reduced.current() > normal.current());
+    }
+
+    #[test]
+    fn reduced_motion_timeline() {
+        use super::{
+            set_reduced_motion, set_reduced_motion_scale, Percentage, ReducedMotion, Timeline,
+        };
+
+        set_reduced_motion(ReducedMotion::Reduce);
+
+        let clock = ManualClock::new();
+        set_clock(clock.clone());
+
+        let timeline = Timeline::new();
+        let track = timeline.then(1000.0);
+
+        timeline.play();
+        assert_eq!(track.current_percentage(), Percentage::END);
+
+        let essential = Timeline::new();
+        let essential_track = essential.then(1000.0);
+
+        essential.set_essential(true);
+        essential.play();
+        assert_eq!(essential_track.current_percentage(), Percentage::START);
+
+        set_reduced_motion_scale(0.5);
+
+        timeline.seek(0.0);
+        timeline.play();
+
+        clock.advance(0.0);
+        clock.advance(250.0);
+        assert_eq!(track.current_percentage(), Percentage::new(0.5));
+        assert_eq!(essential_track.current_percentage(), Percentage::new(0.25));
+    }
+
+    #[wasm_bindgen_test]
+    fn reduced_motion_web_animation() {
+        use js_sys::{Function, Reflect};
+        use wasm_bindgen::{JsCast, JsValue};
+
+        use super::{
+            set_reduced_motion, set_reduced_motion_scale, ReducedMotion, WebAnimation,
+            WebAnimationOptions, WebKeyframes,
+        };
+        use crate::bindings;
+
+        let duration = |animation: &WebAnimation| {
+            let get =
+                |object: &JsValue, name: &str| Reflect::get(object, &JsValue::from(name)).unwrap();
+
+            let effect = get(animation.raw(), "effect");
+            let get_timing: Function = get(&effect, "getTiming").unchecked_into();
+            get(&get_timing.call0(&effect).unwrap(), "duration").as_f64()
+        };
+
+        let element = bindings::create_element("div");
+        let keyframes = WebKeyframes::new()
+            .keyframe(|frame| frame.style("opacity", "0"))
+            .keyframe(|frame| frame.style("opacity", "1"));
+        let options = WebAnimationOptions {
+            duration: 1000.0,
+            ..WebAnimationOptions::default()
+        };
+
+        set_reduced_motion(ReducedMotion::Reduce);
+
+        let animation = WebAnimation::new(&element, &keyframes, &options).unwrap();
+        assert_eq!(duration(&animation), Some(0.0));
+
+        set_reduced_motion_scale(0.5);
+
+        let animation = WebAnimation::new(&element, &keyframes, &options).unwrap();
+        assert_eq!(duration(&animation), Some(500.0));
+
+        let essential = WebAnimationOptions {
+            essential: true,
+            ..options.clone()
+        };
+
+        let animation = WebAnimation::new(&element, &keyframes, &essential).unwrap();
+        assert_eq!(duration(&animation), Some(1000.0));
+
+        set_reduced_motion(ReducedMotion::NoPreference);
+
+        let animation = WebAnimation::new(&element, &keyframes, &options).unwrap();
+        assert_eq!(duration(&animation), Some(1000.0));
+    }
+
+    #[wasm_bindgen_test]
+    async fn web_animation_current_time_signal() {
+        use futures_signals::signal::SignalExt;
+        use futures_util::stream::StreamExt;
+
+        use super::{
+            set_reduced_motion, ReducedMotion, WebAnimation, WebAnimationOptions, WebKeyframes,
+        };
+        use crate::bindings;
+
+        set_reduced_motion(ReducedMotion::NoPreference);
+
+        let element = bindings::create_element("div");
+        let keyframes = WebKeyframes::new()
+            .keyframe(|frame| frame.style("opacity", "0"))
+            .keyframe(|frame| frame.style("opacity", "1"));
+        let options = WebAnimationOptions {
+            duration: 50.0,
+            ..WebAnimationOptions::default()
+        };
+
+        let animation = WebAnimation::new(&element, &keyframes, &options).unwrap();
+
+        // The signal ends after the animation finishes
+        let times: Vec<_> = animation.current_time_signal().to_stream().collect().await;
+
+        assert_eq!(times.last(), Some(&Some(50.0)));
+    }
+
+    #[test]
+    fn easing() {
+        use super::{
+            easing::{Curve, Easing, StepPosition},
+            Percentage,
+        };
+
+        let parse = |input: &str| input.parse::<Easing>();
+
+        assert_eq!(parse("ease-in-out"), Ok(Easing::EaseInOut));
+        assert_eq!(
+            parse("cubic-bezier(0.68, -0.55, 0.27, 1.55)"),
+            Ok(Easing::CubicBezier(0.68, -0.55, 0.27, 1.55)),
+        );
+        assert_eq!(
+            parse("steps(4)"),
+            Ok(Easing::Steps(4, StepPosition::JumpEnd))
+        );
+        assert_eq!(
+            parse("step-start"),
+            Ok(Easing::Steps(1, StepPosition::JumpStart))
+        );
+        assert_eq!(parse("ease-in-out-sine"), Ok(Easing::InOut(Curve::Sine)));
+        assert!(parse("cubic-bezier(2, 0, 0, 1)").is_err());
+        assert!(parse("cubic-bezier(0, NaN, 0, 1)").is_err());
+        assert!(parse("cubic-bezier(0, 0, 0, inf)").is_err());
+        assert!(parse("steps(1, jump-none)").is_err());
+        assert!(parse("ease-in-foo").is_err());
+
+        for easing in [
+            Easing::Ease,
+            Easing::CubicBezier(0.1, 0.2, 0.3, 0.4),
+            Easing::Steps(3, StepPosition::JumpBoth),
+            Easing::Out(Curve::Bounce),
+        ] {
+            assert_eq!(parse(&easing.to_string()), Ok(easing));
+        }
+
+        let at = |easing: Easing, p: f64| easing.easing(Percentage::new(p)).into_f64();
+
+        for easing in [
+            Easing::Ease,
+            Easing::In(Curve::Elastic),
+            Easing::Out(Curve::Bounce),
+        ] {
+            assert!(at(easing, 0.0).abs() < 1e-6);
+            assert!((at(easing, 1.0) - 1.0).abs() < 1e-6);
+        }
+
+        assert_eq!(at(Easing::Steps(4, StepPosition::JumpEnd), 0.3), 0.25);
+        assert_eq!(at(Easing::Steps(4, StepPosition::JumpStart), 0.3), 0.5);
+        assert_eq!(at(Easing::Steps(3, StepPosition::JumpNone), 0.5), 0.5);
+        assert!(at(Easing::In(Curve::Back), 0.2) < 0.0);
+
+        // Invalid values are normalised instead of panicking
+        assert_eq!(at(Easing::CubicBezier(0.0, f64::NAN, 1.0, 1.0), 0.3), 0.3);
+        assert!((at(Easing::CubicBezier(2.0, 0.0, -1.0, 1.0), 1.0) - 1.0).abs() < 1e-6);
+        assert_eq!(at(Easing::Steps(0, StepPosition::JumpEnd), 0.5), 0.0);
+        assert_eq!(at(Easing::Steps(1, StepPosition::JumpNone), 0.5), 0.0);
+    }
+}
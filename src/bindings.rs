@@ -1,8 +1,9 @@
 use js_sys::Reflect;
 use wasm_bindgen::{intern, prelude::*, JsCast};
 use web_sys::{
-    Comment, CssRule, CssStyleDeclaration, CssStyleSheet, Document, DomTokenList, Element, History,
-    HtmlElement, HtmlStyleElement, Node, Text, Window,
+    Animation, Comment, CssGroupingRule, CssKeyframeRule, CssKeyframesRule, CssRule,
+    CssStyleDeclaration, CssStyleRule, CssStyleSheet, Document, DomTokenList, Element, History,
+    HtmlElement, HtmlStyleElement, MediaQueryList, Node, NodeList, ShadowRoot, Text, Window,
 };
 
 use crate::utils::UnwrapJsExt;
@@ -16,7 +17,7 @@ pub(crate) fn set_property(obj: &JsValue, name: &str, value: &JsValue) {
 
 thread_local! {
     pub static WINDOW: Window = web_sys::window().unwrap_throw();
-    static DOCUMENT: Document = WINDOW.with(|w| w.document().unwrap_throw());
+    pub static DOCUMENT: Document = WINDOW.with(|w| w.document().unwrap_throw());
     static HISTORY: History = WINDOW.with(|w| w.history().unwrap_js());
 }
 
@@ -28,11 +29,20 @@ pub(crate) fn ready_state() -> String {
     DOCUMENT.with(|d| d.ready_state())
 }
 
+pub(crate) fn is_hidden() -> bool {
+    DOCUMENT.with(|d| d.hidden())
+}
+
 #[track_caller]
 pub(crate) fn current_url() -> String {
     WINDOW.with(|w| w.location().href().unwrap_js())
 }
 
+#[track_caller]
+pub(crate) fn match_media(query: &str) -> Option<MediaQueryList> {
+    WINDOW.with(|w| w.match_media(query).unwrap_js())
+}
+
 #[track_caller]
 pub(crate) fn go_to_url(url: &str) {
     HISTORY.with(|h| {
@@ -70,6 +80,33 @@ pub(crate) fn create_stylesheet(css: Option<&str>) -> CssStyleSheet {
     })
 }
 
+thread_local! {
+    // Each shadow root only has one stylesheet, it is a WeakMap so it doesn't keep the shadow root alive
+    static SHADOW_STYLESHEETS: js_sys::WeakMap = js_sys::WeakMap::new();
+}
+
+// This uses a constructable stylesheet, because a <style> inside of the shadow
+// root doesn't have a stylesheet until the shadow root is inserted into the DOM
+#[track_caller]
+pub(crate) fn adopt_stylesheet(shadow: &ShadowRoot) -> CssStyleSheet {
+    SHADOW_STYLESHEETS.with(|stylesheets| {
+        let existing = stylesheets.get(shadow);
+
+        if !existing.is_undefined() {
+            return existing.unchecked_into();
+        }
+
+        let sheet = CssStyleSheet::new().unwrap_js();
+        // Older browsers don't allow mutating adoptedStyleSheets, so it creates a new array
+        let sheets = shadow
+            .adopted_style_sheets()
+            .concat(&js_sys::Array::of1(&sheet));
+        shadow.set_adopted_style_sheets(&sheets);
+        stylesheets.set(shadow, &sheet);
+        sheet
+    })
+}
+
 #[track_caller]
 pub(crate) fn make_rule(sheet: &CssStyleSheet, rule: &str) -> Result<CssRule, JsValue> {
     let rules = sheet.css_rules().unwrap_js();
@@ -80,6 +117,66 @@ pub(crate) fn make_rule(sheet: &CssStyleSheet, rule: &str) -> Result<CssRule, Js
     Ok(rules.get(length).unwrap_throw())
 }
 
+#[track_caller]
+pub(crate) fn make_nested_rule(group: &CssGroupingRule, rule: &str) -> Result<CssRule, JsValue> {
+    let rules = group.css_rules();
+    let length = rules.length();
+    group.insert_rule_with_index(rule, length)?;
+    Ok(rules.get(length).unwrap_throw())
+}
+
+// This is used as a placeholder when a rule is invalid, the styles are set but they do nothing
+#[track_caller]
+pub(crate) fn detached_style() -> CssStyleDeclaration {
+    create_element("div")
+        .unchecked_into::<HtmlElement>()
+        .style()
+}
+
+pub(crate) fn append_keyframe(
+    rule: &CssKeyframesRule,
+    keyframe: &str,
+) -> Option<CssStyleDeclaration> {
+    let rules = rule.css_rules();
+    let length = rules.length();
+    rule.append_rule(keyframe);
+    // Invalid keyframes are silently ignored, so it checks whether it was appended or not
+    rules
+        .get(length)
+        .map(|keyframe| keyframe.unchecked_into::<CssKeyframeRule>().style())
+}
+
+// Returns the style rule which contains the declaration, and also the grouping
+// rule (such as `@media`) which contains the style rule
+pub(crate) fn parent_rules(style: &CssStyleDeclaration) -> (CssStyleRule, Option<CssGroupingRule>) {
+    let rule: CssStyleRule = style.parent_rule().unwrap_throw().unchecked_into();
+    let group = rule.parent_rule().map(|group| group.unchecked_into());
+    (rule, group)
+}
+
+#[track_caller]
+pub(crate) fn delete_rule(sheet: &CssStyleSheet, rule: &CssRule) {
+    let rules = sheet.css_rules().unwrap_js();
+
+    // The index of the rule changes when other rules are deleted, so it has to search for it
+    // TODO make this more efficient ?
+    for index in (0..rules.length()).rev() {
+        if rules.get(index).as_ref() == Some(rule) {
+            sheet.delete_rule(index).unwrap_js();
+            return;
+        }
+    }
+}
+
+pub(crate) fn active_element() -> Option<Element> {
+    DOCUMENT.with(|d| d.active_element())
+}
+
+#[track_caller]
+pub(crate) fn query_selector_all(elem: &Element, selector: &str) -> NodeList {
+    elem.query_selector_all(selector).unwrap_js()
+}
+
 pub(crate) fn get_element_by_id(id: &str) -> Element {
     DOCUMENT.with(|d| d.get_element_by_id(id).unwrap_throw())
 }
@@ -203,3 +300,26 @@ pub(crate) fn focus(elem: &HtmlElement) {
 pub(crate) fn blur(elem: &HtmlElement) {
     elem.blur().unwrap_js();
 }
+
+#[wasm_bindgen]
+extern "C" {
+    // This is used because `Element.animate` is unstable in web-sys
+    type AnimatableElement;
+
+    #[wasm_bindgen(method, catch)]
+    fn animate(
+        this: &AnimatableElement,
+        keyframes: &JsValue,
+        options: &JsValue,
+    ) -> Result<Animation, JsValue>;
+}
+
+#[inline]
+pub(crate) fn animate(
+    elem: &Element,
+    keyframes: &JsValue,
+    options: &JsValue,
+) -> Result<Animation, JsValue> {
+    elem.unchecked_ref::<AnimatableElement>()
+        .animate(keyframes, options)
+}
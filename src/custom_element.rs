@@ -0,0 +1,433 @@
+use std::{cell::RefCell, rc::Rc};
+
+use discard::Discard;
+use futures_signals::signal::{Mutable, Signal};
+use js_sys::{Array, Object};
+use wasm_bindgen::{closure::Closure, prelude::*};
+use web_sys::{HtmlElement, ShadowRoot, ShadowRootMode};
+
+use crate::{append_dom, bindings, utils::UnwrapJsExt, Dom, DomHandle};
+
+#[wasm_bindgen(inline_js = r#"
+// These are not stored on the element, because then any script could access
+// them, which would defeat a closed shadow root
+const instances = new WeakMap();
+const shadows = new WeakMap();
+
+export function define_custom_element(name, attributes, properties, mode, create) {
+    class DominatorElement extends HTMLElement {
+        static get observedAttributes() {
+            return attributes;
+        }
+
+        constructor() {
+            super();
+            // The element is only passed to Rust when it is needed, so Rust doesn't keep it alive
+            instances.set(this, create(this));
+
+            // Properties which were set before the element was upgraded
+            for (const property of properties) {
+                if (Object.prototype.hasOwnProperty.call(this, property)) {
+                    const value = this[property];
+                    delete this[property];
+                    this[property] = value;
+                }
+            }
+        }
+
+        connectedCallback() {
+            // A shadow root can only be attached once, so it is reused when the element is moved
+            let shadow = shadows.get(this);
+
+            if (shadow === undefined) {
+                shadow = this.attachShadow({ mode });
+                shadows.set(this, shadow);
+            }
+
+            instances.get(this).connected(this, shadow);
+        }
+
+        disconnectedCallback() {
+            instances.get(this).disconnected();
+        }
+
+        attributeChangedCallback(name, old_value, value) {
+            instances.get(this).attribute(name, value);
+        }
+    }
+
+    for (const property of properties) {
+        Object.defineProperty(DominatorElement.prototype, property, {
+            configurable: true,
+            enumerable: true,
+            get() {
+                return instances.get(this).get(property);
+            },
+            set(value) {
+                instances.get(this).set(property, value);
+            },
+        });
+    }
+
+    customElements.define(name, DominatorElement);
+}
+"#)]
+extern "C" {
+    #[wasm_bindgen(catch)]
+    fn define_custom_element(
+        name: &str,
+        attributes: Array,
+        properties: Array,
+        mode: ShadowRootMode,
+        create: &JsValue,
+    ) -> Result<(), JsValue>;
+}
+
+/// The host element of a [`CustomElement`].
+///
+/// This is passed to the `render` function of [`CustomElement::define`].
+#[derive(Debug)]
+pub struct Host {
+    element: HtmlElement,
+    attributes: Vec<(String, Mutable<Option<String>>)>,
+    properties: Vec<(String, Mutable<JsValue>)>,
+}
+
+impl Host {
+    /// Returns the custom element.
+    #[inline]
+    pub fn element(&self) -> &HtmlElement {
+        &self.element
+    }
+
+    /// Returns a `Signal` of the value of an observed attribute.
+    ///
+    /// If the attribute doesn't exist then the value is `None`.
+    ///
+    /// The attribute must have been added with [`CustomElement::attribute`].
+    #[track_caller]
+    pub fn attribute_signal(&self, name: &str) -> impl Signal<Item = Option<String>> {
+        if let Some((_, value)) = self.attributes.iter().find(|(x, _)| x == name) {
+            value.signal_cloned()
+        } else {
+            panic!("{} is not an observed attribute", name);
+        }
+    }
+
+    /// Returns a `Signal` of the value of a property.
+    ///
+    /// If the property hasn't been set then the value is `undefined`.
+    ///
+    /// The property must have been added with [`CustomElement::property`].
+    #[track_caller]
+    pub fn property_signal(&self, name: &str) -> impl Signal<Item = JsValue> {
+        if let Some((_, value)) = self.properties.iter().find(|(x, _)| x == name) {
+            value.signal_cloned()
+        } else {
+            panic!("{} is not a property", name);
+        }
+    }
+}
+
+type Render = Rc<dyn Fn(&Host) -> Dom>;
+
+// This must not contain the element or the shadow root. The element contains the
+// callbacks which contain the `Instance`, so that would be a cycle between Rust and
+// JS which can never be garbage collected.
+struct Instance {
+    attributes: Vec<(String, Mutable<Option<String>>)>,
+    properties: Vec<(String, Mutable<JsValue>)>,
+    render: Render,
+    handle: RefCell<Option<DomHandle>>,
+}
+
+impl Instance {
+    #[track_caller]
+    fn connected(&self, element: HtmlElement, shadow: ShadowRoot) {
+        if self.handle.borrow().is_some() {
+            return;
+        }
+
+        let host = Host {
+            element,
+            attributes: self.attributes.clone(),
+            properties: self.properties.clone(),
+        };
+
+        let dom = (self.render)(&host);
+
+        *self.handle.borrow_mut() = Some(append_dom(&shadow, dom));
+    }
+
+    #[track_caller]
+    fn disconnected(&self) {
+        let handle = self.handle.borrow_mut().take();
+
+        if let Some(handle) = handle {
+            handle.discard();
+        }
+    }
+
+    fn set_attribute(&self, name: &str, value: Option<String>) {
+        if let Some((_, mutable)) = self.attributes.iter().find(|(x, _)| x == name) {
+            mutable.set_neq(value);
+        }
+    }
+
+    fn property(&self, name: &str) -> JsValue {
+        self.properties
+            .iter()
+            .find(|(x, _)| x == name)
+            .map(|(_, value)| value.get_cloned())
+            .unwrap_or(JsValue::UNDEFINED)
+    }
+
+    fn set_property(&self, name: &str, value: JsValue) {
+        if let Some((_, mutable)) = self.properties.iter().find(|(x, _)| x == name) {
+            mutable.set(value);
+        }
+    }
+}
+
+fn to_array(values: &[String]) -> Array {
+    values.iter().map(|x| JsValue::from(x.as_str())).collect()
+}
+
+/// Defines a [custom element](https://developer.mozilla.org/en-US/docs/Web/API/Web_components/Using_custom_elements)
+/// which renders a [`Dom`] into its shadow root.
+///
+/// This lets you use dominator components with other frameworks (or with
+/// plain HTML):
+///
+/// ```rust
+/// CustomElement::new("my-counter")
+///     .attribute("label")
+///     .property("count")
+///     .define(|host| {
+///         html!("div", {
+///             .text_signal(host.attribute_signal("label").map(|x| x.unwrap_or_default()))
+///             .text_signal(host.property_signal("count").map(|x| {
+///                 x.as_f64().unwrap_or(0.0).to_string()
+///             }))
+///         })
+///     });
+/// ```
+///
+/// When the element is inserted into the page, the `render` function is
+/// called and the [`Dom`] is appended into the element's shadow root.
+///
+/// When the element is removed from the page, the [`Dom`] is discarded. If the
+/// element is inserted again then `render` is called again.
+#[derive(Debug)]
+pub struct CustomElement {
+    name: String,
+    attributes: Vec<String>,
+    properties: Vec<String>,
+    mode: ShadowRootMode,
+}
+
+impl CustomElement {
+    /// The `name` must contain a `-`, for example `"my-element"`.
+    #[inline]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            attributes: vec![],
+            properties: vec![],
+            mode: ShadowRootMode::Open,
+        }
+    }
+
+    /// Observes an attribute, which can be used with [`Host::attribute_signal`].
+    #[inline]
+    pub fn attribute(mut self, name: &str) -> Self {
+        self.attributes.push(name.to_owned());
+        self
+    }
+
+    /// Adds a JavaScript property to the element, which can be used with
+    /// [`Host::property_signal`].
+    #[inline]
+    pub fn property(mut self, name: &str) -> Self {
+        self.properties.push(name.to_owned());
+        self
+    }
+
+    /// Sets the mode of the shadow root, the default is [`ShadowRootMode::Open`].
+    #[inline]
+    pub fn shadow_root(mut self, mode: ShadowRootMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Registers the custom element with the browser.
+    ///
+    /// This panics if the name is invalid or if it has already been defined.
+    #[track_caller]
+    pub fn define<F>(self, render: F)
+    where
+        F: Fn(&Host) -> Dom + 'static,
+    {
+        let render: Render = Rc::new(render);
+        let attributes = self.attributes.clone();
+        let properties = self.properties.clone();
+
+        let create = Closure::wrap(Box::new(move |element: HtmlElement| -> JsValue {
+            let instance = Rc::new(Instance {
+                attributes: attributes
+                    .iter()
+                    .map(|name| (name.clone(), Mutable::new(element.get_attribute(name))))
+                    .collect(),
+                properties: properties
+                    .iter()
+                    .map(|name| (name.clone(), Mutable::new(JsValue::UNDEFINED)))
+                    .collect(),
+                render: render.clone(),
+                handle: RefCell::new(None),
+            });
+
+            let callbacks: JsValue = Object::new().into();
+
+            {
+                let instance = instance.clone();
+                let f: Box<dyn FnMut(HtmlElement, ShadowRoot)> =
+                    Box::new(move |element, shadow| instance.connected(element, shadow));
+                let f = Closure::wrap(f);
+                bindings::set_property(&callbacks, "connected", &f.into_js_value());
+            }
+
+            {
+                let instance = instance.clone();
+                let f =
+                    Closure::wrap(Box::new(move || instance.disconnected()) as Box<dyn FnMut()>);
+                bindings::set_property(&callbacks, "disconnected", &f.into_js_value());
+            }
+
+            {
+                let instance = instance.clone();
+                let f = Closure::wrap(Box::new(move |name: String, value: Option<String>| {
+                    instance.set_attribute(&name, value);
+                }) as Box<dyn FnMut(String, Option<String>)>);
+                bindings::set_property(&callbacks, "attribute", &f.into_js_value());
+            }
+
+            {
+                let instance = instance.clone();
+                let f = Closure::wrap(Box::new(move |name: String| instance.property(&name))
+                    as Box<dyn FnMut(String) -> JsValue>);
+                bindings::set_property(&callbacks, "get", &f.into_js_value());
+            }
+
+            {
+                let f = Closure::wrap(Box::new(move |name: String, value: JsValue| {
+                    instance.set_property(&name, value);
+                }) as Box<dyn FnMut(String, JsValue)>);
+                bindings::set_property(&callbacks, "set", &f.into_js_value());
+            }
+
+            callbacks
+        }) as Box<dyn FnMut(HtmlElement) -> JsValue>);
+
+        define_custom_element(
+            &self.name,
+            to_array(&self.attributes),
+            to_array(&self.properties),
+            self.mode,
+            // This is leaked because the custom element is defined forever
+            &create.into_js_value(),
+        )
+        .unwrap_js();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use futures_signals::signal::SignalExt;
+    use js_sys::{Object, Promise, Reflect};
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_futures::JsFuture;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::HtmlElement;
+
+    use super::CustomElement;
+    use crate::{body, DomBuilder};
+
+    // Waits for the signals to update the DOM
+    async fn tick() {
+        JsFuture::from(Promise::resolve(&JsValue::UNDEFINED))
+            .await
+            .unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    async fn define() {
+        CustomElement::new("dominator-test-counter")
+            .attribute("label")
+            .property("count")
+            .define(|host| {
+                html!("div", {
+                    .text_signal(host.attribute_signal("label").map(|x| x.unwrap_or_default()))
+                    .text_signal(host.property_signal("count").map(|x| {
+                        x.as_f64().unwrap_or(0.0).to_string()
+                    }))
+                })
+            });
+
+        let element: HtmlElement = DomBuilder::new_html("dominator-test-counter").element();
+
+        element.set_attribute("label", "count: ").unwrap();
+        Reflect::set(&element, &JsValue::from("count"), &JsValue::from(5)).unwrap();
+
+        body().append_child(&element).unwrap();
+        tick().await;
+
+        let shadow = element.shadow_root().unwrap();
+        assert_eq!(shadow.text_content(), Some("count: 5".to_owned()));
+
+        element.set_attribute("label", "total: ").unwrap();
+        Reflect::set(&element, &JsValue::from("count"), &JsValue::from(10)).unwrap();
+        tick().await;
+
+        assert_eq!(shadow.text_content(), Some("total: 10".to_owned()));
+        assert_eq!(
+            Reflect::get(&element, &JsValue::from("count")).unwrap(),
+            JsValue::from(10)
+        );
+
+        // The internal state isn't visible on the element
+        assert_eq!(Object::keys(&element).length(), 0);
+
+        body().remove_child(&element).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn connect_disconnect() {
+        let removed = Rc::new(Cell::new(false));
+
+        CustomElement::new("dominator-test-element").define({
+            let removed = removed.clone();
+
+            move |_host| {
+                let removed = removed.clone();
+
+                html!("div", {
+                    .after_removed(move |_| removed.set(true))
+                })
+            }
+        });
+
+        let element: HtmlElement = DomBuilder::new_html("dominator-test-element").element();
+
+        body().append_child(&element).unwrap();
+
+        let shadow = element.shadow_root().unwrap();
+        assert_eq!(shadow.child_element_count(), 1);
+
+        body().remove_child(&element).unwrap();
+
+        assert!(removed.get());
+        assert_eq!(shadow.child_element_count(), 0);
+    }
+}
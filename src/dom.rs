@@ -1,36 +1,45 @@
 use std::{
     borrow::BorrowMut,
+    cell::{Cell, RefCell},
+    collections::HashMap,
     convert::AsRef,
+    fmt,
     future::Future,
+    panic::Location,
     pin::Pin,
+    rc::Rc,
     task::{Context, Poll},
 };
 
 use discard::{Discard, DiscardOnDrop};
 use futures_channel::oneshot;
 use futures_signals::{
-    signal::{channel, not, Receiver, Signal},
-    signal_vec::SignalVec,
+    signal::{channel, from_future, not, Receiver, Signal, SignalExt},
+    signal_vec::{SignalVec, SignalVecExt},
 };
 use futures_util::FutureExt;
 use once_cell::sync::Lazy;
 use wasm_bindgen::{intern, JsCast, JsValue, UnwrapThrowExt};
 use web_sys::{
-    CssRule, CssStyleDeclaration, CssStyleRule, CssStyleSheet, Element, EventTarget, HtmlElement,
-    Node, ShadowRoot, ShadowRootInit, ShadowRootMode, Text,
+    CssGroupingRule, CssKeyframesRule, CssRule, CssStyleDeclaration, CssStyleRule, CssStyleSheet,
+    Element, EventTarget, HtmlElement, Node, ShadowRoot, ShadowRootInit, ShadowRootMode, Text,
 };
 
 #[cfg(doc)]
 use crate::fragment;
 use crate::{
+    animation::{AnimatedMapBroadcaster, WebAnimation, WebAnimationOptions, WebKeyframes},
     bindings,
     bindings::WINDOW,
     callbacks::Callbacks,
     fragment::{Fragment, FragmentBuilder},
     operations,
-    operations::{for_each, spawn_future},
+    operations::{for_each, spawn_future, CatchPanic},
     traits::*,
-    utils::{on, EventListener, FnDiscard, UnwrapJsExt, ValueDiscard},
+    utils::{
+        error_message, on, report_js, report_warning, EventListener, FnDiscard, UnwrapJsExt,
+        ValueDiscard,
+    },
 };
 
 pub struct RefFn<A, B, C>
@@ -370,6 +379,51 @@ impl Dom {
     }
 }
 
+/// The error which is given to the fallback of
+/// [`child_signal_result`](DomBuilder::child_signal_result) and
+/// [`children_signal_vec_result`](DomBuilder::children_signal_vec_result).
+///
+/// Panics can only be caught if the program is compiled with
+/// `panic = "unwind"`. Wasm always uses `panic = "abort"`, so in the browser
+/// a panic still aborts the program and only `Err` is caught.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoundaryError<E> {
+    /// The child returned an `Err`.
+    Err(E),
+    /// The child panicked, this contains the panic message.
+    Panic(String),
+}
+
+impl<E> fmt::Display for BoundaryError<E>
+where
+    E: fmt::Debug + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BoundaryError::Err(e) => f.write_str(&error_message(e)),
+            BoundaryError::Panic(message) => write!(f, "panicked: {}", message),
+        }
+    }
+}
+
+fn boundary_fallback<A, E, F>(value: Result<Result<A, E>, String>, fallback: &mut F) -> A
+where
+    E: fmt::Debug + 'static,
+    F: FnMut(BoundaryError<E>) -> A,
+{
+    let error = match value {
+        Ok(Ok(value)) => return value,
+        Ok(Err(e)) => {
+            report_js("Error while creating child", &e);
+            BoundaryError::Err(e)
+        }
+        // The panic hook has already reported the panic
+        Err(message) => BoundaryError::Panic(message),
+    };
+
+    fallback(error)
+}
+
 #[inline]
 #[track_caller]
 fn create_element<A>(name: &str) -> A
@@ -421,6 +475,112 @@ where
     }));
 }
 
+/// What happens when a style or a CSS rule isn't supported by the browser.
+///
+/// This is set with [`set_invalid_style_policy`].
+///
+/// By default it panics, except that invalid styles are ignored in release
+/// builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidStylePolicy {
+    /// Panics with an error message.
+    Panic,
+    /// Logs a warning with `console.warn`.
+    Warn,
+    /// Silently ignores it.
+    Ignore,
+}
+
+/// An invalid style or CSS rule, this is passed to the [`set_invalid_style_hook`] hook.
+#[derive(Debug)]
+pub enum InvalidStyle<'a> {
+    /// None of the style names and values were supported.
+    Style {
+        names: &'a [String],
+        values: &'a [String],
+    },
+    /// None of the CSS rules were supported, for example because of an invalid
+    /// selector or media query.
+    Selectors { rules: &'a [String] },
+}
+
+impl<'a> fmt::Display for InvalidStyle<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidStyle::Style { names, values } => write!(
+                f,
+                "style is incorrect:\n  names: {}\n  values: {}",
+                names.join(", "),
+                values.join(", ")
+            ),
+            InvalidStyle::Selectors { rules } => {
+                write!(f, "selectors are incorrect:\n  {}", rules.join("\n  "))
+            }
+        }
+    }
+}
+
+type InvalidStyleHook = Rc<dyn Fn(&InvalidStyle)>;
+
+thread_local! {
+    static INVALID_STYLE_POLICY: Cell<Option<InvalidStylePolicy>> = const { Cell::new(None) };
+    static INVALID_STYLE_HOOK: RefCell<Option<InvalidStyleHook>> = const { RefCell::new(None) };
+}
+
+/// Sets what happens when a style or a CSS rule isn't supported by the
+/// browser, see [`InvalidStylePolicy`].
+///
+/// Browsers support different styles, so you might want to use
+/// [`InvalidStylePolicy::Warn`] in production.
+#[inline]
+pub fn set_invalid_style_policy(policy: InvalidStylePolicy) {
+    INVALID_STYLE_POLICY.with(|x| x.set(Some(policy)));
+}
+
+/// Sets a hook which is called for every invalid style or CSS rule.
+///
+/// This is called before the [`InvalidStylePolicy`] is applied, so it can be
+/// used for logging or telemetry:
+///
+/// ```rust
+/// set_invalid_style_policy(InvalidStylePolicy::Ignore);
+///
+/// set_invalid_style_hook(|error| {
+///     send_to_telemetry(error.to_string());
+/// });
+/// ```
+#[inline]
+pub fn set_invalid_style_hook<F>(hook: F)
+where
+    F: Fn(&InvalidStyle) + 'static,
+{
+    INVALID_STYLE_HOOK.with(|x| *x.borrow_mut() = Some(Rc::new(hook)));
+}
+
+#[track_caller]
+fn invalid_style(error: InvalidStyle) {
+    // The hook is cloned so that it can call `set_invalid_style_hook`
+    let hook = INVALID_STYLE_HOOK.with(|x| x.borrow().clone());
+
+    if let Some(hook) = hook {
+        hook(&error);
+    }
+
+    let policy = match INVALID_STYLE_POLICY.with(|x| x.get()) {
+        Some(policy) => policy,
+        None => match error {
+            InvalidStyle::Style { .. } if !cfg!(debug_assertions) => InvalidStylePolicy::Ignore,
+            _ => InvalidStylePolicy::Panic,
+        },
+    };
+
+    match policy {
+        InvalidStylePolicy::Panic => panic!("{}", error),
+        InvalidStylePolicy::Warn => report_warning(&error.to_string()),
+        InvalidStylePolicy::Ignore => {}
+    }
+}
+
 // TODO should this inline ?
 // TODO track_caller
 fn set_style<A, B>(style: &CssStyleDeclaration, name: &A, value: B, important: bool)
@@ -442,7 +602,6 @@ where
     ) -> Option<()> {
         assert!(value != "");
 
-        // TODO handle browser prefixes ?
         bindings::remove_style(style, name);
 
         bindings::set_style(style, name, value, important);
@@ -467,15 +626,22 @@ where
         })
     });
 
+    #[cfg(feature = "vendor-prefixes")]
+    let okay = okay.or_else(|| {
+        name.find_map(|name| {
+            value.find_map(|value| {
+                crate::vendor_prefixes::find_map(name, value, |name, value| {
+                    try_set_style(style, &mut names, &mut values, name, value, important)
+                })
+            })
+        })
+    });
+
     if let None = okay {
-        if cfg!(debug_assertions) {
-            // TODO maybe make this configurable
-            panic!(
-                "style is incorrect:\n  names: {}\n  values: {}",
-                names.join(", "),
-                values.join(", ")
-            );
-        }
+        invalid_style(InvalidStyle::Style {
+            names: &names,
+            values: &values,
+        });
     }
 }
 
@@ -501,8 +667,12 @@ fn set_style_signal<A, B, C, D>(
             }
             None => {
                 name.each(|name| {
-                    // TODO handle browser prefixes ?
                     bindings::remove_style(style, intern(name));
+
+                    #[cfg(feature = "vendor-prefixes")]
+                    crate::vendor_prefixes::each_name(name, |name| {
+                        bindings::remove_style(style, name);
+                    });
                 });
             }
         }
@@ -541,6 +711,74 @@ fn set_style_unchecked_signal<A, B, C, D>(
     });
 }
 
+// CSS variables can have any value, so unlike `set_style` they are not checked
+#[track_caller]
+fn set_css_var(style: &CssStyleDeclaration, name: &str, value: &str) {
+    assert!(
+        name.starts_with("--"),
+        "CSS variable must start with --: {}",
+        name
+    );
+
+    // An empty string would remove the variable, but an empty variable is valid
+    let value = if value.is_empty() { " " } else { value };
+
+    bindings::set_style(style, intern(name), value, false);
+}
+
+// TODO should this inline ?
+fn set_css_var_signal<A, B, C, D>(
+    style: CssStyleDeclaration,
+    callbacks: &mut Callbacks,
+    name: A,
+    value: D,
+) where
+    A: AsStr + 'static,
+    B: AsStr,
+    C: OptionStr<Output = B>,
+    D: Signal<Item = C> + 'static,
+{
+    set_option(style, callbacks, value, move |style, value| {
+        name.with_str(|name| match value {
+            Some(value) => {
+                value.with_str(|value| {
+                    set_css_var(style, name, value);
+                });
+            }
+            None => {
+                bindings::remove_style(style, intern(name));
+            }
+        });
+    });
+}
+
+// TODO should this inline ?
+fn set_css_vars_signal<A, B>(style: CssStyleDeclaration, callbacks: &mut Callbacks, value: B)
+where
+    A: CssVars,
+    B: Signal<Item = A> + 'static,
+{
+    let mut previous: Vec<String> = vec![];
+
+    callbacks.after_remove(for_each(value, move |vars| {
+        let mut names = vec![];
+
+        vars.each_var(|name, value| {
+            set_css_var(&style, name, value);
+            names.push(String::from(name));
+        });
+
+        // Removes the variables which are no longer used
+        for name in previous.iter() {
+            if !names.contains(name) {
+                bindings::remove_style(&style, name);
+            }
+        }
+
+        previous = names;
+    }));
+}
+
 // TODO check that the property *actually* was changed ?
 // TODO maybe use AsRef<Object> ?
 // TODO should this inline ?
@@ -601,6 +839,36 @@ impl Default for EventOptions {
     }
 }
 
+/// The order of the transitions for [`child_signal_animated`](DomBuilder::child_signal_animated).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionOrder {
+    /// The new child is inserted after the old child has finished its exit animation.
+    Sequential,
+    /// The new child is inserted immediately, so both children are displayed at the same time.
+    Crossfade,
+}
+
+/// Options for [`children_signal_vec_flip`](DomBuilder::children_signal_vec_flip).
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlipOptions {
+    /// The duration of the animation in milliseconds.
+    pub duration: f64,
+    /// The CSS [easing function](https://developer.mozilla.org/en-US/docs/Web/CSS/easing-function).
+    pub easing: String,
+    /// See [`MutableAnimation::set_essential`](crate::animation::MutableAnimation::set_essential).
+    pub essential: bool,
+}
+
+impl Default for FlipOptions {
+    fn default() -> Self {
+        Self {
+            duration: 300.0,
+            easing: "ease".to_owned(),
+            essential: false,
+        }
+    }
+}
+
 // TODO better warning message for must_use
 #[must_use]
 #[derive(Debug)]
@@ -1007,103 +1275,513 @@ where
             self.element.as_ref().clone(),
             &mut self.callbacks,
             children,
+            None,
         );
         self
     }
-}
 
-impl<A> DomBuilder<A>
-where
-    A: AsRef<Element>,
-{
+    /// The same as [`children_signal_vec`](DomBuilder::children_signal_vec)
+    /// except the children smoothly move to their new position when the list changes.
+    ///
+    /// This uses the [FLIP](https://aerotwist.com/blog/flip-your-animations/)
+    /// technique: the position of the children is measured before and after
+    /// the changes, and then the difference is animated with a `transform`.
+    ///
+    /// This animates the children which move because of inserts, removes, or
+    /// moves. The inserted and removed children themselves are not animated,
+    /// you can use [`animated_map`](crate::animation::AnimatedSignalVec::animated_map) for that:
+    ///
+    /// ```rust
+    /// html!("ul", {
+    ///     .children_signal_vec_flip(FlipOptions::default(), state.items.signal_vec_cloned()
+    ///         .animated_map(500.0, |item, animation| {
+    ///             html!("li", {
+    ///                 .style_signal("opacity", animation.signal().map(|t| t.into_f64().to_string()))
+    ///                 .text(&item.name)
+    ///             })
+    ///         }))
+    /// })
+    /// ```
+    ///
+    /// The animations respect [reduced motion](crate::animation::set_reduced_motion).
+    ///
+    /// If the browser rejects the animation (for example an invalid `easing`)
+    /// then the error is logged to the console and the children move without
+    /// animating.
     #[inline]
-    #[doc(hidden)]
     #[track_caller]
-    pub fn __internal_shadow_root(&self, mode: ShadowRootMode) -> DomBuilder<ShadowRoot> {
-        let shadow = self
-            .element
-            .as_ref()
-            .attach_shadow(&ShadowRootInit::new(mode))
-            .unwrap_js();
-        DomBuilder::new(shadow)
+    pub fn children_signal_vec_flip<B>(mut self, options: FlipOptions, children: B) -> Self
+    where
+        B: SignalVec<Item = Dom> + 'static,
+    {
+        operations::insert_children_signal_vec(
+            self.element.as_ref().clone(),
+            &mut self.callbacks,
+            children,
+            Some(options),
+        );
+        self
+    }
+
+    /// The same as [`child_signal`](DomBuilder::child_signal) except it
+    /// animates the child when it is inserted or removed.
+    ///
+    /// The function `f` is called to create the child, and it receives an
+    /// animation which goes from `0.0` to `1.0` when the child is inserted,
+    /// and from `1.0` to `0.0` when it is removed:
+    ///
+    /// ```rust
+    /// html!("div", {
+    ///     .child_signal_animated(300.0, TransitionOrder::Crossfade, state.page.signal(), |page, animation| {
+    ///         html!("div", {
+    ///             .style_signal("opacity", animation.signal().map(|t| t.into_f64().to_string()))
+    ///             .text(&page.title)
+    ///         })
+    ///     })
+    /// })
+    /// ```
+    ///
+    /// The old child stays in the DOM until its exit animation has finished.
+    /// With [`TransitionOrder::Crossfade`] both children are in the DOM at the
+    /// same time, so you will usually want to use `position: absolute` for them.
+    #[inline]
+    #[track_caller]
+    pub fn child_signal_animated<B, C, F>(
+        mut self,
+        duration: f64,
+        order: TransitionOrder,
+        child: C,
+        f: F,
+    ) -> Self
+    where
+        C: Signal<Item = Option<B>> + 'static,
+        F: FnMut(B, AnimatedMapBroadcaster) -> Dom + 'static,
+    {
+        operations::insert_child_signal_animated(
+            self.element.as_ref().clone(),
+            &mut self.callbacks,
+            child,
+            duration,
+            order,
+            f,
+        );
+        self
     }
 
+    /// Inserts the child into the `target` node instead of into this
+    /// [`DomBuilder`].
+    ///
+    /// This is useful for modals, tooltips, and popups which must be inside of
+    /// [`body`] for stacking reasons, but which are owned by a deeply nested
+    /// component:
+    ///
+    /// ```rust
+    /// html!("div", {
+    ///     .portal(&body(), state.is_open.signal().map(|is_open| {
+    ///         if is_open {
+    ///             Some(html!("div", { .class(&*MODAL_CLASS) }))
+    ///         } else {
+    ///             None
+    ///         }
+    ///     }))
+    /// })
+    /// ```
+    ///
+    /// The child behaves exactly the same as [`child_signal`](DomBuilder::child_signal),
+    /// except it is only inserted into `target` after this [`DomBuilder`] is
+    /// inserted, and it is removed from `target` when this [`DomBuilder`] is
+    /// removed.
     #[inline]
     #[track_caller]
-    pub fn attr<B>(self, name: B, value: &str) -> Self
+    pub fn portal<B>(mut self, target: &Node, child: B) -> Self
     where
-        B: MultiStr,
+        B: Signal<Item = Option<Dom>> + 'static,
     {
-        let element = self.element.as_ref();
-
-        name.each(|name| {
-            bindings::set_attribute(element, intern(name), &value);
-        });
-
+        operations::insert_portal_signal(target.clone(), &mut self.callbacks, child);
         self
     }
 
-    #[deprecated(since = "0.5.24", note = "Use the `attr` method instead")]
+    /// The same as [`child_signal`](DomBuilder::child_signal) except the
+    /// signal returns a `Result`.
+    ///
+    /// If the signal returns an `Err` then the error is logged to the console
+    /// (the same as other dominator errors) and the `fallback` is displayed
+    /// instead:
+    ///
+    /// ```rust
+    /// html!("div", {
+    ///     .child_signal_result(state.user.signal_cloned().map(|user| {
+    ///         let user = parse_user(&user)?;
+    ///         Ok(Some(html!("span", { .text(&user.name) })))
+    ///     }), |error| {
+    ///         Some(html!("span", { .text(&format!("Invalid user: {}", error)) }))
+    ///     })
+    /// })
+    /// ```
+    ///
+    /// **Panics are not caught on wasm.** Wasm always uses `panic = "abort"`, so
+    /// in the browser a panic inside of the signal aborts the program, the
+    /// same as with [`child_signal`](DomBuilder::child_signal). Only `Err` is
+    /// turned into the `fallback`.
+    ///
+    /// On targets which use `panic = "unwind"` (such as native tests) panics
+    /// are also caught. After a panic the signal is no longer polled, so the
+    /// fallback stays displayed.
     #[inline]
     #[track_caller]
-    pub fn attribute<B>(self, name: B, value: &str) -> Self
+    pub fn child_signal_result<B, E, F>(self, child: B, mut fallback: F) -> Self
     where
-        B: MultiStr,
+        B: Signal<Item = Result<Option<Dom>, E>> + 'static,
+        E: fmt::Debug + 'static,
+        F: FnMut(BoundaryError<E>) -> Option<Dom> + 'static,
     {
-        self.attr(name, value)
+        self.child_signal(
+            CatchPanic::new(child).map(move |child| boundary_fallback(child, &mut fallback)),
+        )
     }
 
+    /// The same as [`children_signal_vec`](DomBuilder::children_signal_vec)
+    /// except the children are `Result`s.
+    ///
+    /// If a child is an `Err` then the error is logged to the console and the
+    /// `fallback` is displayed in place of that child.
+    ///
+    /// **Panics are not caught on wasm**, see
+    /// [`child_signal_result`](DomBuilder::child_signal_result). On targets
+    /// which use `panic = "unwind"`, if the signal panics then all of the
+    /// children are replaced with a single `fallback`, and the signal is no
+    /// longer polled.
     #[inline]
     #[track_caller]
-    pub fn attr_ns<B>(self, namespace: &str, name: B, value: &str) -> Self
+    pub fn children_signal_vec_result<B, E, F>(self, children: B, mut fallback: F) -> Self
     where
-        B: MultiStr,
+        B: SignalVec<Item = Result<Dom, E>> + 'static,
+        E: fmt::Debug + 'static,
+        F: FnMut(BoundaryError<E>) -> Dom + 'static,
     {
-        let element = self.element.as_ref();
-        let namespace: &str = intern(namespace);
-
-        name.each(|name| {
-            bindings::set_attribute_ns(element, &namespace, intern(name), &value);
-        });
-
-        self
+        self.children_signal_vec(
+            CatchPanic::new(children).map(move |child| boundary_fallback(child, &mut fallback)),
+        )
     }
 
-    #[deprecated(since = "0.5.24", note = "Use the `attr_ns` method instead")]
+    /// Displays the `fallback` until the `future` resolves, and then displays
+    /// the [`Dom`] which is returned by the `future`:
+    ///
+    /// ```rust
+    /// html!("div", {
+    ///     .child_future(html!("span", { .text("Loading...") }), async move {
+    ///         let user = fetch_user().await;
+    ///         html!("span", { .text(&user.name) })
+    ///     })
+    /// })
+    /// ```
+    ///
+    /// If this [`DomBuilder`] is removed before the `future` resolves, then
+    /// the `future` is cancelled.
     #[inline]
     #[track_caller]
-    pub fn attribute_namespace<B>(self, namespace: &str, name: B, value: &str) -> Self
+    pub fn child_future<B>(self, fallback: Dom, future: B) -> Self
     where
-        B: MultiStr,
+        B: Future<Output = Dom> + 'static,
     {
-        self.attr_ns(namespace, name, value)
+        let mut fallback = Some(fallback);
+
+        self.child_signal(from_future(future).map(move |child| match child {
+            Some(child) => Some(child),
+            None => fallback.take(),
+        }))
     }
 
+    /// The same as [`child_future`](DomBuilder::child_future) except the
+    /// `future` can fail.
+    ///
+    /// If the `future` returns an `Err` then the `error` closure is called and
+    /// its [`Dom`] is displayed instead. This is the same as
+    /// [`child_signal_result`](DomBuilder::child_signal_result).
     #[inline]
     #[track_caller]
-    pub fn class<B>(self, name: B) -> Self
+    pub fn child_future_result<B, E, F>(self, fallback: Dom, future: B, error: F) -> Self
+    where
+        B: Future<Output = Result<Dom, E>> + 'static,
+        E: fmt::Debug + 'static,
+        F: FnOnce(BoundaryError<E>) -> Dom + 'static,
+    {
+        let mut fallback = Some(fallback);
+        let mut error = Some(error);
+
+        self.child_signal_result(
+            from_future(future).map(move |child| match child {
+                Some(Ok(child)) => Ok(Some(child)),
+                Some(Err(e)) => Err(e),
+                None => Ok(fallback.take()),
+            }),
+            move |e| error.take().map(|error| error(e)),
+        )
+    }
+
+    /// Displays the `fallback` until the `future` resolves, and then displays
+    /// the children which are returned by the `future`.
+    ///
+    /// If this [`DomBuilder`] is removed before the `future` resolves, then
+    /// the `future` is cancelled.
+    #[inline]
+    #[track_caller]
+    pub fn children_future<B>(self, fallback: Dom, future: B) -> Self
     where
-        B: MultiStr,
+        B: Future<Output = Vec<Dom>> + 'static,
     {
-        let classes = self.element.as_ref().class_list();
-
-        name.each(|name| {
-            bindings::add_class(&classes, intern(name));
-        });
+        let mut fallback = Some(fallback);
 
-        self
+        self.children_signal_vec(
+            from_future(future)
+                .map(move |children| match children {
+                    Some(children) => children,
+                    None => fallback.take().into_iter().collect(),
+                })
+                .to_signal_vec(),
+        )
     }
+}
 
-    // TODO make this more efficient ?
+impl DomBuilder<ShadowRoot> {
+    /// Scopes the styles to this shadow root.
+    ///
+    /// While the closure is running, [`class!`](crate::class) and
+    /// [`stylesheet!`](crate::stylesheet) (and the other stylesheet macros)
+    /// insert their rules into a stylesheet which only applies inside of the
+    /// shadow root, instead of the global stylesheet:
+    ///
+    /// ```rust
+    /// html!("my-component", {
+    ///     .shadow_root!(ShadowRootMode::Open => {
+    ///         .scoped_styles(|dom| {
+    ///             stylesheet!(":host", {
+    ///                 .style("display", "block")
+    ///             });
+    ///
+    ///             let button = class! {
+    ///                 .style("color", "green")
+    ///             };
+    ///
+    ///             dom.child(html!("button", {
+    ///                 .class(&button)
+    ///             }))
+    ///         })
+    ///     })
+    /// })
+    /// ```
+    ///
+    /// The stylesheet is added to the shadow root's [`adoptedStyleSheets`](https://developer.mozilla.org/en-US/docs/Web/API/ShadowRoot/adoptedStyleSheets),
+    /// so the rules are removed when the shadow root is garbage collected. Each
+    /// shadow root only has one stylesheet, so calling `scoped_styles` again
+    /// (for example when a [`CustomElement`](crate::custom_element::CustomElement)
+    /// is inserted again) reuses the same stylesheet. However, the
+    /// `style_signal` listeners are leaked, so you should use
+    /// [`class_handle!`](crate::class_handle) with [`DomBuilder::scoped_class`]
+    /// for signals.
+    ///
+    /// The rules only exist inside of this shadow root, so you must create the
+    /// classes inside of the closure. You must not use a class which is shared
+    /// with other code, such as a `static` class which uses `Lazy`: if the
+    /// `Lazy` is first used inside of the closure then its rules will only be
+    /// inserted into this shadow root, and every other place which uses the
+    /// class will not have any styles.
+    ///
+    /// [`static_class!`](crate::static_class) and [`static_stylesheet!`](crate::static_stylesheet)
+    /// are not affected, they always use the global stylesheet.
     #[inline]
     #[track_caller]
-    pub fn visible(self, value: bool) -> Self {
-        if value {
-            // TODO remove the class somehow ?
-            self
-        } else {
-            self.class(&*HIDDEN_CLASS)
-        }
+    pub fn scoped_styles<F>(self, f: F) -> Self
+    where
+        F: FnOnce(Self) -> Self,
+    {
+        let stylesheet = bindings::adopt_stylesheet(&self.element);
+        in_stylesheet(Some(stylesheet), move || f(self))
+    }
+}
+
+impl<A> DomBuilder<A>
+where
+    A: AsRef<Element>,
+{
+    #[inline]
+    #[doc(hidden)]
+    #[track_caller]
+    pub fn __internal_shadow_root(&self, mode: ShadowRootMode) -> DomBuilder<ShadowRoot> {
+        let shadow = self
+            .element
+            .as_ref()
+            .attach_shadow(&ShadowRootInit::new(mode))
+            .unwrap_js();
+        DomBuilder::new(shadow)
+    }
+
+    /// Runs an animation with the [Web Animations API](https://developer.mozilla.org/en-US/docs/Web/API/Web_Animations_API).
+    ///
+    /// The animation is run by the browser, so it is faster than animating
+    /// with [`style_signal`](DomBuilder::style_signal):
+    ///
+    /// ```rust
+    /// html!("div", {
+    ///     .web_animation(
+    ///         WebKeyframes::new()
+    ///             .keyframe(|frame| frame.style("opacity", "0").style("transform", "scale(0.5)"))
+    ///             .keyframe(|frame| frame.style("opacity", "1").style("transform", "none")),
+    ///         WebAnimationOptions {
+    ///             duration: 300.0,
+    ///             easing: "ease-out".to_owned(),
+    ///             ..WebAnimationOptions::default()
+    ///         },
+    ///         |animation| {
+    ///             spawn_local(async move {
+    ///                 if animation.finished().await.is_ok() {
+    ///                     log!("Finished");
+    ///                 }
+    ///             });
+    ///         }
+    ///     )
+    /// })
+    /// ```
+    ///
+    /// The animation starts after the element is inserted into the DOM, and then
+    /// `f` is called with the [`WebAnimation`](crate::animation::WebAnimation).
+    ///
+    /// When the element is removed from the DOM the animation is cancelled.
+    ///
+    /// If the browser rejects the keyframes or the options then the error is
+    /// logged to the console and `f` is not called.
+    ///
+    /// The duration respects [reduced motion](crate::animation::set_reduced_motion).
+    #[inline]
+    #[track_caller]
+    pub fn web_animation<F>(
+        mut self,
+        keyframes: WebKeyframes,
+        options: WebAnimationOptions,
+        f: F,
+    ) -> Self
+    where
+        F: FnOnce(WebAnimation) + 'static,
+    {
+        let element = self.element.as_ref().clone();
+        let location = Location::caller();
+
+        self.callbacks.after_insert(move |callbacks| {
+            match WebAnimation::new(&element, &keyframes, &options) {
+                Ok(animation) => {
+                    callbacks.after_remove(FnDiscard::new({
+                        let animation = animation.clone();
+                        move || animation.cancel()
+                    }));
+
+                    f(animation);
+                }
+                Err(e) => {
+                    report_js(&format!("Invalid web_animation at {}", location), &e);
+                }
+            }
+        });
+
+        self
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn attr<B>(self, name: B, value: &str) -> Self
+    where
+        B: MultiStr,
+    {
+        let element = self.element.as_ref();
+
+        name.each(|name| {
+            bindings::set_attribute(element, intern(name), &value);
+        });
+
+        self
+    }
+
+    #[deprecated(since = "0.5.24", note = "Use the `attr` method instead")]
+    #[inline]
+    #[track_caller]
+    pub fn attribute<B>(self, name: B, value: &str) -> Self
+    where
+        B: MultiStr,
+    {
+        self.attr(name, value)
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn attr_ns<B>(self, namespace: &str, name: B, value: &str) -> Self
+    where
+        B: MultiStr,
+    {
+        let element = self.element.as_ref();
+        let namespace: &str = intern(namespace);
+
+        name.each(|name| {
+            bindings::set_attribute_ns(element, &namespace, intern(name), &value);
+        });
+
+        self
+    }
+
+    #[deprecated(since = "0.5.24", note = "Use the `attr_ns` method instead")]
+    #[inline]
+    #[track_caller]
+    pub fn attribute_namespace<B>(self, namespace: &str, name: B, value: &str) -> Self
+    where
+        B: MultiStr,
+    {
+        self.attr_ns(namespace, name, value)
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn class<B>(self, name: B) -> Self
+    where
+        B: MultiStr,
+    {
+        let classes = self.element.as_ref().class_list();
+
+        name.each(|name| {
+            bindings::add_class(&classes, intern(name));
+        });
+
+        self
+    }
+
+    /// Adds the class from a [`ClassHandle`], and then removes the CSS rules
+    /// for the class when this [`DomBuilder`] is removed.
+    ///
+    /// This is useful for classes which are created for a single component
+    /// instance:
+    ///
+    /// ```rust
+    /// html!("div", {
+    ///     .scoped_class(class_handle! {
+    ///         .style_signal("color", state.color.signal_cloned())
+    ///     })
+    /// })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn scoped_class(mut self, handle: ClassHandle) -> Self {
+        bindings::add_class(&self.element.as_ref().class_list(), &handle.class_name);
+        self.callbacks.after_remove(handle);
+        self
+    }
+
+    // TODO make this more efficient ?
+    #[inline]
+    #[track_caller]
+    pub fn visible(self, value: bool) -> Self {
+        if value {
+            // TODO remove the class somehow ?
+            self
+        } else {
+            self.class(&*HIDDEN_CLASS)
+        }
     }
 }
 
@@ -1331,6 +2009,38 @@ where
         self.set_scroll_signal(signal, Element::set_scroll_top);
         self
     }
+
+    /// Calls the closure whenever the user presses a pointer outside of this
+    /// element.
+    ///
+    /// This is useful for closing dropdowns, popups, and menus:
+    ///
+    /// ```rust
+    /// html!("div", {
+    ///     .click_outside(clone!(state => move |_| {
+    ///         state.is_open.set_neq(false);
+    ///     }))
+    /// })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn click_outside<F>(self, mut listener: F) -> Self
+    where
+        F: FnMut(crate::events::PointerDown) + 'static,
+    {
+        let element: Element = self.element.as_ref().clone();
+
+        self.global_event(move |e: crate::events::PointerDown| {
+            let is_inside = e
+                .dyn_target::<Node>()
+                .map(|target| element.contains(Some(&target)))
+                .unwrap_or(false);
+
+            if !is_inside {
+                listener(e);
+            }
+        })
+    }
 }
 
 impl<A> DomBuilder<A>
@@ -1436,6 +2146,60 @@ where
         self
     }
 
+    /// Sets a [CSS variable](https://developer.mozilla.org/en-US/docs/Web/CSS/Using_CSS_custom_properties).
+    ///
+    /// The name must start with `--`. Unlike [`style`](DomBuilder::style), the
+    /// value is not checked, and it can be empty.
+    #[inline]
+    #[track_caller]
+    pub fn css_var<B, C>(self, name: B, value: C) -> Self
+    where
+        B: AsStr,
+        C: AsStr,
+    {
+        name.with_str(|name| {
+            value.with_str(|value| {
+                set_css_var(&self.element.as_ref().style(), name, value);
+            });
+        });
+        self
+    }
+
+    /// The same as [`css_var`](DomBuilder::css_var) except the value is a
+    /// `Signal`.
+    ///
+    /// If the `Signal` returns `None` then the CSS variable is removed.
+    #[inline]
+    pub fn css_var_signal<B, C, D, E>(mut self, name: B, value: E) -> Self
+    where
+        B: AsStr + 'static,
+        C: AsStr,
+        D: OptionStr<Output = C>,
+        E: Signal<Item = D> + 'static,
+    {
+        set_css_var_signal(
+            self.element.as_ref().style(),
+            &mut self.callbacks,
+            name,
+            value,
+        );
+        self
+    }
+
+    /// Sets all of the CSS variables from a [`CssVars`] `Signal`.
+    ///
+    /// When the `Signal` changes, any CSS variables which are no longer used
+    /// are removed.
+    #[inline]
+    pub fn css_vars_signal<B, C>(mut self, value: C) -> Self
+    where
+        B: CssVars,
+        C: Signal<Item = B> + 'static,
+    {
+        set_css_vars_signal(self.element.as_ref().style(), &mut self.callbacks, value);
+        self
+    }
+
     // TODO remove the `value` argument ?
     #[inline]
     #[track_caller]
@@ -1488,6 +2252,161 @@ where
         self.set_focused_signal(value);
         self
     }
+
+    /// Keeps the keyboard focus inside of this element.
+    ///
+    /// When the element is inserted it will move the focus to the first
+    /// focusable child. While the element exists, pressing `Tab` or
+    /// `Shift+Tab` will cycle through the focusable children, and focus
+    /// which escapes the element is moved back into it.
+    ///
+    /// If there aren't any focusable children then the element itself is
+    /// focused instead (with `tabindex="-1"`).
+    ///
+    /// Traps can be nested (such as a confirm dialog inside of a modal), in
+    /// which case only the most recently inserted trap is active. When the
+    /// element is removed, the previous trap becomes active again and the
+    /// focus is restored to whichever element had the focus before the trap
+    /// was inserted.
+    ///
+    /// This is useful for modal dialogs:
+    ///
+    /// ```rust
+    /// html!("div", {
+    ///     .attr("role", "dialog")
+    ///     .focus_trap()
+    ///     .children(&mut [
+    ///         html!("input"),
+    ///         html!("button", { .text("Close") }),
+    ///     ])
+    /// })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn focus_trap(mut self) -> Self {
+        let element: HtmlElement = self.element.as_ref().clone();
+
+        {
+            let element = element.clone();
+
+            // This needs to use `after_insert` because calling `.focus()` on an element
+            // before it is in the DOM has no effect
+            self.callbacks.after_insert(move |callbacks| {
+                let previous = bindings::active_element()
+                    .and_then(|previous| previous.dyn_into::<HtmlElement>().ok());
+
+                FOCUS_TRAPS.with(|traps| traps.borrow_mut().push(element.clone()));
+
+                if !contains_focus(&element) {
+                    focus_first(&element);
+                }
+
+                callbacks.after_remove(FnDiscard::new(move || {
+                    FOCUS_TRAPS.with(|traps| traps.borrow_mut().retain(|trap| *trap != element));
+
+                    if let Some(previous) = previous {
+                        if previous.is_connected() {
+                            bindings::focus(&previous);
+                        }
+                    }
+                }));
+            });
+        }
+
+        {
+            let target = element.clone();
+            let element = element.clone();
+
+            Self::_event(
+                &mut self.callbacks,
+                &target,
+                &EventOptions::preventable(),
+                move |e: crate::events::KeyDown| {
+                    if e.key() == "Tab" && is_active_trap(&element) {
+                        let focusable = focusable_elements(&element);
+
+                        match (focusable.first(), focusable.last()) {
+                            (Some(first), Some(last)) => {
+                                let is_inside = contains_focus(&element);
+
+                                if e.shift_key() {
+                                    if !is_inside || is_focused(first) {
+                                        e.prevent_default();
+                                        bindings::focus(last);
+                                    }
+                                } else if !is_inside || is_focused(last) {
+                                    e.prevent_default();
+                                    bindings::focus(first);
+                                }
+                            }
+                            // There is nothing to focus, so keep the focus where it is
+                            _ => {
+                                e.prevent_default();
+                            }
+                        }
+                    }
+                },
+            );
+        }
+
+        self.global_event(move |e: crate::events::FocusIn| {
+            // Only the most recently inserted trap moves the focus, otherwise nested traps
+            // would keep stealing the focus from each other forever
+            if is_active_trap(&element) {
+                let is_inside = e
+                    .dyn_target::<Node>()
+                    .map(|target| element.contains(Some(&target)))
+                    .unwrap_or(false);
+
+                if !is_inside {
+                    focus_first(&element);
+                }
+            }
+        })
+    }
+}
+
+thread_local! {
+    // The elements which are using `focus_trap`, the last element is the active trap
+    static FOCUS_TRAPS: RefCell<Vec<HtmlElement>> = const { RefCell::new(vec![]) };
+}
+
+fn is_active_trap(element: &HtmlElement) -> bool {
+    FOCUS_TRAPS.with(|traps| traps.borrow().last() == Some(element))
+}
+
+fn focus_first(element: &HtmlElement) {
+    if let Some(first) = focusable_elements(element).first() {
+        bindings::focus(first);
+    } else {
+        // If there is nothing to focus then the focus would escape, so focus the container instead
+        if !element.has_attribute(intern("tabindex")) {
+            bindings::set_attribute(element, intern("tabindex"), intern("-1"));
+        }
+
+        bindings::focus(element);
+    }
+}
+
+const FOCUSABLE_SELECTOR: &str = "a[href], area[href], button:not([disabled]), input:not([disabled]):not([type=\"hidden\"]), select:not([disabled]), textarea:not([disabled]), iframe, [contenteditable], [tabindex]:not([tabindex=\"-1\"])";
+
+fn focusable_elements(element: &Element) -> Vec<HtmlElement> {
+    let nodes = bindings::query_selector_all(element, intern(FOCUSABLE_SELECTOR));
+
+    (0..nodes.length())
+        .filter_map(|index| nodes.get(index))
+        .filter_map(|node| node.dyn_into::<HtmlElement>().ok())
+        .collect()
+}
+
+fn is_focused(element: &Element) -> bool {
+    bindings::active_element().as_ref() == Some(element)
+}
+
+fn contains_focus(element: &Element) -> bool {
+    bindings::active_element()
+        .map(|active| element.contains(Some(&active)))
+        .unwrap_or(false)
 }
 
 /// Creates a raw global CSS stylesheet.
@@ -1531,30 +2450,100 @@ where
     });
 }
 
-// TODO better warning message for must_use
-#[must_use]
-pub struct StylesheetBuilder {
-    element: CssStyleDeclaration,
-    callbacks: Callbacks,
+fn with_stylesheet<A, F>(f: F) -> A
+where
+    F: FnOnce(&CssStyleSheet) -> A,
+{
+    // TODO can this be made faster ?
+    // TODO somehow share this safely between threads ?
+    thread_local! {
+        static STYLESHEET: CssStyleSheet = bindings::create_stylesheet(None);
+    }
+
+    let scoped = SCOPED_STYLESHEET.with(|scoped| scoped.borrow().clone());
+
+    if let Some(stylesheet) = scoped {
+        f(&stylesheet)
+    } else {
+        STYLESHEET.with(f)
+    }
 }
 
-// TODO remove the CssStyleRule when this is discarded
-impl StylesheetBuilder {
-    fn __internal_rules<A>(rules: &A) -> CssRule
-    where
-        A: MultiStr,
-    {
-        // TODO can this be made faster ?
-        // TODO somehow share this safely between threads ?
-        thread_local! {
-            static STYLESHEET: CssStyleSheet = bindings::create_stylesheet(None);
-        }
+thread_local! {
+    // This is used by `scoped_styles` to insert the rules into a shadow root
+    static SCOPED_STYLESHEET: RefCell<Option<CssStyleSheet>> = const { RefCell::new(None) };
+}
 
-        STYLESHEET.with(move |stylesheet| {
-            let mut failed = vec![];
+// Runs `f`, and while it is running all of the rules are inserted into `stylesheet`
+fn in_stylesheet<A, F>(stylesheet: Option<CssStyleSheet>, f: F) -> A
+where
+    F: FnOnce() -> A,
+{
+    struct Restore(Option<CssStyleSheet>);
 
-            let okay = rules.find_map(|rule| {
-                // TODO maybe intern the rule ?
+    impl Drop for Restore {
+        #[inline]
+        fn drop(&mut self) {
+            let old = self.0.take();
+            SCOPED_STYLESHEET.with(|scoped| *scoped.borrow_mut() = old);
+        }
+    }
+
+    let _restore = Restore(SCOPED_STYLESHEET.with(|scoped| scoped.replace(stylesheet)));
+
+    f()
+}
+
+/// This is returned by the [`stylesheet_handle!`] macro.
+///
+/// When the [`StylesheetHandle`] is discarded using `handle.discard()` it
+/// will remove the CSS rules from the stylesheet and it will stop all of the
+/// `style_signal` listeners.
+///
+/// If you never call `handle.discard()` then it will leak memory forever,
+/// which is the same as [`stylesheet!`].
+#[must_use]
+#[derive(Debug)]
+pub struct StylesheetHandle {
+    rules: Vec<CssRule>,
+    callbacks: Callbacks,
+}
+
+impl Discard for StylesheetHandle {
+    #[inline]
+    #[track_caller]
+    fn discard(self) {
+        let rules = self.rules;
+
+        self.callbacks.discard();
+
+        for rule in rules {
+            // The rule might be in a shadow root stylesheet
+            if let Some(stylesheet) = rule.parent_style_sheet() {
+                bindings::delete_rule(&stylesheet, &rule);
+            }
+        }
+    }
+}
+
+// TODO better warning message for must_use
+#[must_use]
+pub struct StylesheetBuilder {
+    element: CssStyleDeclaration,
+    rules: Vec<CssRule>,
+    callbacks: Callbacks,
+}
+
+impl StylesheetBuilder {
+    fn __internal_rules<A>(rules: &A) -> CssRule
+    where
+        A: MultiStr,
+    {
+        with_stylesheet(move |stylesheet| {
+            let mut failed = vec![];
+
+            let okay = rules.find_map(|rule| {
+                // TODO maybe intern the rule ?
                 if let Ok(declaration) = bindings::make_rule(stylesheet, rule) {
                     Some(declaration)
                 } else {
@@ -1566,8 +2555,10 @@ impl StylesheetBuilder {
             if let Some(okay) = okay {
                 okay
             } else {
-                // TODO maybe make this configurable
-                panic!("selectors are incorrect:\n  {}", failed.join("\n  "));
+                invalid_style(InvalidStyle::Selectors { rules: &failed });
+
+                // This rule never matches, so the styles don't do anything
+                bindings::make_rule(stylesheet, ":not(*) {}").unwrap_js()
             }
         })
     }
@@ -1578,14 +2569,28 @@ impl StylesheetBuilder {
     where
         A: MultiStr,
     {
-        let element = Self::__internal_rules(&rules).unchecked_into::<CssStyleRule>();
+        let rule = Self::__internal_rules(&rules);
+        let element = rule.unchecked_ref::<CssStyleRule>().style();
 
         Self {
-            element: element.style(),
+            element,
+            rules: vec![rule],
             callbacks: Callbacks::new(),
         }
     }
 
+    // This is used by `pseudo!` so that the rules are owned by the class
+    #[inline]
+    fn __internal_transfer(&mut self, mut other: StylesheetBuilder) {
+        self.rules.append(&mut other.rules);
+        self.callbacks
+            .after_insert
+            .append(&mut other.callbacks.after_insert);
+        self.callbacks
+            .after_remove
+            .append(&mut other.callbacks.after_remove);
+    }
+
     #[doc(hidden)]
     #[inline]
     pub fn __internal_new<A>(rules: A) -> Self
@@ -1683,6 +2688,55 @@ impl StylesheetBuilder {
         self
     }
 
+    /// Sets a [CSS variable](https://developer.mozilla.org/en-US/docs/Web/CSS/Using_CSS_custom_properties).
+    ///
+    /// The name must start with `--`. Unlike [`style`](StylesheetBuilder::style), the
+    /// value is not checked, and it can be empty.
+    #[inline]
+    #[track_caller]
+    pub fn css_var<B, C>(self, name: B, value: C) -> Self
+    where
+        B: AsStr,
+        C: AsStr,
+    {
+        name.with_str(|name| {
+            value.with_str(|value| {
+                set_css_var(&self.element, name, value);
+            });
+        });
+        self
+    }
+
+    /// The same as [`css_var`](StylesheetBuilder::css_var) except the value is a
+    /// `Signal`.
+    ///
+    /// If the `Signal` returns `None` then the CSS variable is removed.
+    #[inline]
+    pub fn css_var_signal<B, C, D, E>(mut self, name: B, value: E) -> Self
+    where
+        B: AsStr + 'static,
+        C: AsStr,
+        D: OptionStr<Output = C>,
+        E: Signal<Item = D> + 'static,
+    {
+        set_css_var_signal(self.element.clone(), &mut self.callbacks, name, value);
+        self
+    }
+
+    /// Sets all of the CSS variables from a [`CssVars`] `Signal`.
+    ///
+    /// When the `Signal` changes, any CSS variables which are no longer used
+    /// are removed.
+    #[inline]
+    pub fn css_vars_signal<B, C>(mut self, value: C) -> Self
+    where
+        B: CssVars,
+        C: Signal<Item = B> + 'static,
+    {
+        set_css_vars_signal(self.element.clone(), &mut self.callbacks, value);
+        self
+    }
+
     /// Appends raw CSS code into the stylesheet.
     ///
     /// It is recommended to use the various `.style` methods instead.
@@ -1714,15 +2768,194 @@ impl StylesheetBuilder {
         self
     }
 
-    // TODO return a Handle
+    #[track_caller]
+    fn grouping_rule<B, F>(mut self, kind: &str, conditions: B, f: F) -> Self
+    where
+        B: MultiStr,
+        F: FnOnce(StylesheetBuilder) -> StylesheetBuilder,
+    {
+        let (rule, parent) = bindings::parent_rules(&self.element);
+        let selector = rule.selector_text();
+
+        let mut failed = vec![];
+
+        let group = conditions.find_map(|condition| {
+            let css = format!("{} {} {{ {} {{}} }}", kind, condition, selector);
+
+            // If this is already inside of a grouping rule then it is nested inside of it
+            let result = match &parent {
+                Some(parent) => bindings::make_nested_rule(parent, &css),
+                None => with_stylesheet(|stylesheet| bindings::make_rule(stylesheet, &css)),
+            };
+
+            if let Ok(group) = result {
+                Some(group)
+            } else {
+                failed.push(css);
+                None
+            }
+        });
+
+        let group: CssGroupingRule = if let Some(group) = group {
+            group.unchecked_into()
+        } else {
+            invalid_style(InvalidStyle::Selectors { rules: &failed });
+
+            // This rule never matches, so the styles don't do anything
+            let css = format!("@media not all {{ {} {{}} }}", selector);
+
+            match &parent {
+                Some(parent) => bindings::make_nested_rule(parent, &css),
+                None => with_stylesheet(|stylesheet| bindings::make_rule(stylesheet, &css)),
+            }
+            .unwrap_js()
+            .unchecked_into()
+        };
+
+        let inner = f(StylesheetBuilder {
+            element: group
+                .css_rules()
+                .get(0)
+                .unwrap_throw()
+                .unchecked_ref::<CssStyleRule>()
+                .style(),
+            rules: vec![],
+            callbacks: Callbacks::new(),
+        });
+
+        // Nested rules are removed when their parent rule is removed
+        if parent.is_none() {
+            self.rules.push(group.unchecked_into());
+        }
+
+        self.__internal_transfer(inner);
+        self
+    }
+
+    /// Adds styles which only apply when the [media query](https://developer.mozilla.org/en-US/docs/Web/CSS/Media_Queries/Using_media_queries)
+    /// matches.
+    ///
+    /// The styles are added by the closure, which can use all of the
+    /// [`StylesheetBuilder`] methods (including `.media`):
+    ///
+    /// ```rust
+    /// stylesheet!(".foo", {
+    ///     .style("width", "50%")
+    ///     .media("(max-width: 600px)", |s| s
+    ///         .style("width", "100%"))
+    /// })
+    /// ```
+    ///
+    /// If the first argument is an array of strings, it will try each query
+    /// in order until it finds one that works.
+    #[inline]
+    #[track_caller]
+    pub fn media<B, F>(self, query: B, f: F) -> Self
+    where
+        B: MultiStr,
+        F: FnOnce(StylesheetBuilder) -> StylesheetBuilder,
+    {
+        self.grouping_rule("@media", query, f)
+    }
+
+    /// Adds styles which only apply when the browser supports the
+    /// [`@supports`](https://developer.mozilla.org/en-US/docs/Web/CSS/@supports)
+    /// condition.
+    ///
+    /// ```rust
+    /// stylesheet!(".foo", {
+    ///     .style("display", "block")
+    ///     .supports("(display: grid)", |s| s
+    ///         .style("display", "grid"))
+    /// })
+    /// ```
+    ///
+    /// If the first argument is an array of strings, it will try each condition
+    /// in order until it finds one that works.
+    #[inline]
+    #[track_caller]
+    pub fn supports<B, F>(self, condition: B, f: F) -> Self
+    where
+        B: MultiStr,
+        F: FnOnce(StylesheetBuilder) -> StylesheetBuilder,
+    {
+        self.grouping_rule("@supports", condition, f)
+    }
+
+    /// Adds styles which only apply when the [container query](https://developer.mozilla.org/en-US/docs/Web/CSS/CSS_container_queries)
+    /// matches.
+    ///
+    /// ```rust
+    /// stylesheet!(".foo", {
+    ///     .container("sidebar (min-width: 400px)", |s| s
+    ///         .style("display", "flex"))
+    /// })
+    /// ```
+    ///
+    /// If the first argument is an array of strings, it will try each query
+    /// in order until it finds one that works.
+    #[inline]
+    #[track_caller]
+    pub fn container<B, F>(self, query: B, f: F) -> Self
+    where
+        B: MultiStr,
+        F: FnOnce(StylesheetBuilder) -> StylesheetBuilder,
+    {
+        self.grouping_rule("@container", query, f)
+    }
+
+    #[inline]
+    #[track_caller]
+    #[doc(hidden)]
+    pub fn __internal_done(self) {
+        // This leaks the rules and callbacks forever
+        let _ = self.__internal_handle();
+    }
+
     #[inline]
     #[track_caller]
     #[doc(hidden)]
-    pub fn __internal_done(mut self) {
+    pub fn __internal_handle(mut self) -> StylesheetHandle {
         self.callbacks.trigger_after_insert();
 
         // This prevents it from triggering after_remove
         self.callbacks.leak();
+
+        StylesheetHandle {
+            rules: self.rules,
+            callbacks: self.callbacks,
+        }
+    }
+}
+
+/// This is returned by the [`class_handle!`] macro.
+///
+/// It is the same as a [`StylesheetHandle`], except it also contains the
+/// class name. The class name can be retrieved with
+/// [`class_name`](ClassHandle::class_name).
+///
+/// Usually you will use [`DomBuilder::scoped_class`], which will
+/// automatically discard the [`ClassHandle`] when the [`DomBuilder`] is
+/// removed.
+#[must_use]
+#[derive(Debug)]
+pub struct ClassHandle {
+    stylesheet: StylesheetHandle,
+    class_name: String,
+}
+
+impl ClassHandle {
+    #[inline]
+    pub fn class_name(&self) -> &str {
+        &self.class_name
+    }
+}
+
+impl Discard for ClassHandle {
+    #[inline]
+    #[track_caller]
+    fn discard(self) {
+        self.stylesheet.discard();
     }
 }
 
@@ -1730,7 +2963,10 @@ impl StylesheetBuilder {
 #[must_use]
 pub struct ClassBuilder {
     stylesheet: StylesheetBuilder,
-    class_name: String,
+    prefix: Option<String>,
+    // This is `None` until the class needs a rule, so that identical classes can reuse
+    // an existing class without inserting anything
+    class_name: Option<String>,
 }
 
 impl ClassBuilder {
@@ -1738,20 +2974,43 @@ impl ClassBuilder {
     #[inline]
     #[track_caller]
     pub fn __internal_new(name: Option<&str>) -> Self {
-        let class_name = __internal::make_class_id(name);
-
         Self {
-            // TODO make this more efficient ?
-            stylesheet: StylesheetBuilder::__internal_stylesheet(&format!(".{} {{}}", class_name)),
-            class_name,
+            // The static styles are checked with a detached declaration, and then they are
+            // copied into the rule when it is created
+            stylesheet: StylesheetBuilder {
+                element: bindings::detached_style(),
+                rules: vec![],
+                callbacks: Callbacks::new(),
+            },
+            prefix: name.map(String::from),
+            class_name: None,
+        }
+    }
+
+    #[track_caller]
+    fn make_rule(&mut self) -> &str {
+        if self.class_name.is_none() {
+            let class_name = __internal::make_class_id(self.prefix.as_deref());
+
+            let stylesheet = StylesheetBuilder::__internal_stylesheet(format!(
+                ".{} {{ {} }}",
+                class_name,
+                self.stylesheet.element.css_text()
+            ));
+
+            self.stylesheet.element = stylesheet.element;
+            self.stylesheet.rules = stylesheet.rules;
+            self.class_name = Some(class_name);
         }
+
+        self.class_name.as_deref().unwrap_throw()
     }
 
     #[doc(hidden)]
     #[inline]
     #[track_caller]
-    pub fn __internal_class_name(&self) -> &str {
-        &self.class_name
+    pub fn __internal_class_name(&mut self) -> &str {
+        self.make_rule()
     }
 
     #[inline]
@@ -1796,6 +3055,7 @@ impl ClassBuilder {
         D: OptionStr<Output = C>,
         E: Signal<Item = D> + 'static,
     {
+        self.make_rule();
         self.stylesheet = self.stylesheet.style_signal(name, value);
         self
     }
@@ -1809,6 +3069,7 @@ impl ClassBuilder {
         D: OptionStr<Output = C>,
         E: Signal<Item = D> + 'static,
     {
+        self.make_rule();
         self.stylesheet = self.stylesheet.style_important_signal(name, value);
         self
     }
@@ -1822,10 +3083,51 @@ impl ClassBuilder {
         D: OptionStr<Output = C>,
         E: Signal<Item = D> + 'static,
     {
+        self.make_rule();
         self.stylesheet = self.stylesheet.style_unchecked_signal(name, value);
         self
     }
 
+    /// Sets a CSS variable in the class.
+    ///
+    /// See [`StylesheetBuilder::css_var`] for more details.
+    #[inline]
+    #[track_caller]
+    pub fn css_var<B, C>(mut self, name: B, value: C) -> Self
+    where
+        B: AsStr,
+        C: AsStr,
+    {
+        self.stylesheet = self.stylesheet.css_var(name, value);
+        self
+    }
+
+    /// See [`StylesheetBuilder::css_var_signal`] for more details.
+    #[inline]
+    pub fn css_var_signal<B, C, D, E>(mut self, name: B, value: E) -> Self
+    where
+        B: AsStr + 'static,
+        C: AsStr,
+        D: OptionStr<Output = C>,
+        E: Signal<Item = D> + 'static,
+    {
+        self.make_rule();
+        self.stylesheet = self.stylesheet.css_var_signal(name, value);
+        self
+    }
+
+    /// See [`StylesheetBuilder::css_vars_signal`] for more details.
+    #[inline]
+    pub fn css_vars_signal<B, C>(mut self, value: C) -> Self
+    where
+        B: CssVars,
+        C: Signal<Item = B> + 'static,
+    {
+        self.make_rule();
+        self.stylesheet = self.stylesheet.css_vars_signal(value);
+        self
+    }
+
     /// Appends raw CSS code into the class.
     ///
     /// It is recommended to use the various `.style` methods instead.
@@ -1854,28 +3156,294 @@ impl ClassBuilder {
         self
     }
 
-    // TODO return a Handle ?
-    #[doc(hidden)]
+    /// Adds styles to the class which only apply when the media query matches.
+    ///
+    /// ```rust
+    /// class! {
+    ///     .style("width", "50%")
+    ///     .media("(max-width: 600px)", |s| s
+    ///         .style("width", "100%"))
+    /// }
+    /// ```
+    ///
+    /// See [`StylesheetBuilder::media`] for more details.
     #[inline]
     #[track_caller]
-    pub fn __internal_done(self) -> String {
-        self.stylesheet.__internal_done();
-        self.class_name
+    pub fn media<B, F>(mut self, query: B, f: F) -> Self
+    where
+        B: MultiStr,
+        F: FnOnce(StylesheetBuilder) -> StylesheetBuilder,
+    {
+        self.make_rule();
+        self.stylesheet = self.stylesheet.media(query, f);
+        self
     }
-}
 
-#[doc(hidden)]
-pub mod __internal {
-    use std::sync::atomic::{AtomicU32, Ordering};
+    /// Adds styles to the class which only apply when the browser supports
+    /// the condition.
+    ///
+    /// See [`StylesheetBuilder::supports`] for more details.
+    #[inline]
+    #[track_caller]
+    pub fn supports<B, F>(mut self, condition: B, f: F) -> Self
+    where
+        B: MultiStr,
+        F: FnOnce(StylesheetBuilder) -> StylesheetBuilder,
+    {
+        self.make_rule();
+        self.stylesheet = self.stylesheet.supports(condition, f);
+        self
+    }
 
-    pub use web_sys::{HtmlElement, SvgElement};
+    /// Adds styles to the class which only apply when the container query
+    /// matches.
+    ///
+    /// See [`StylesheetBuilder::container`] for more details.
+    #[inline]
+    #[track_caller]
+    pub fn container<B, F>(mut self, query: B, f: F) -> Self
+    where
+        B: MultiStr,
+        F: FnOnce(StylesheetBuilder) -> StylesheetBuilder,
+    {
+        self.make_rule();
+        self.stylesheet = self.stylesheet.container(query, f);
+        self
+    }
 
-    use crate::{
-        fragment::{BoxFragment, Fragment, FragmentBuilder},
-        traits::MultiStr,
-    };
+    #[doc(hidden)]
+    #[inline]
+    #[track_caller]
+    pub fn __internal_pseudo(mut self, stylesheet: StylesheetBuilder) -> Self {
+        self.make_rule();
+        self.stylesheet.__internal_transfer(stylesheet);
+        self
+    }
 
-    pub fn make_class_id(name: Option<&str>) -> String {
+    #[doc(hidden)]
+    #[inline]
+    #[track_caller]
+    pub fn __internal_done(mut self) -> String {
+        self.make_rule();
+        self.stylesheet.__internal_done();
+        self.class_name.unwrap_throw()
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    #[track_caller]
+    pub fn __internal_done_dedup(mut self) -> String {
+        if let Some(class_name) = self.existing_class_name() {
+            return class_name;
+        }
+
+        self.make_rule();
+        self.stylesheet.__internal_done();
+        self.class_name.unwrap_throw()
+    }
+
+    // Returns the class name of an existing class which has the same styles, otherwise
+    // it creates the rule and remembers it for the next identical class
+    #[track_caller]
+    fn existing_class_name(&mut self) -> Option<String> {
+        thread_local! {
+            static CLASSES: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+        }
+
+        // Classes with signals, pseudo rules, or grouping rules already have their own
+        // rule, and scoped classes are in a different stylesheet
+        if self.class_name.is_some() || SCOPED_STYLESHEET.with(|scoped| scoped.borrow().is_some()) {
+            return None;
+        }
+
+        let css_text = self.stylesheet.element.css_text();
+
+        // Empty classes are usually used as markers, so they must stay unique
+        if css_text.is_empty() {
+            return None;
+        }
+
+        // Classes with a different prefix are not reused
+        let key = format!("{}\n{}", self.prefix.as_deref().unwrap_or(""), css_text);
+
+        if let Some(class_name) = CLASSES.with(|classes| classes.borrow().get(&key).cloned()) {
+            return Some(class_name);
+        }
+
+        let class_name = String::from(self.make_rule());
+
+        CLASSES.with(|classes| classes.borrow_mut().insert(key, class_name));
+
+        None
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    #[track_caller]
+    pub fn __internal_handle(mut self) -> ClassHandle {
+        self.make_rule();
+
+        ClassHandle {
+            stylesheet: self.stylesheet.__internal_handle(),
+            class_name: self.class_name.unwrap_throw(),
+        }
+    }
+}
+
+// TODO better warning message for must_use
+#[must_use]
+pub struct KeyframesBuilder {
+    name: String,
+    rule: CssKeyframesRule,
+    callbacks: Callbacks,
+}
+
+impl KeyframesBuilder {
+    #[doc(hidden)]
+    #[inline]
+    pub fn __internal_new(name: Option<&str>) -> Self {
+        let name = __internal::make_class_id(Some(name.unwrap_or("__keyframes_")));
+
+        let rule = StylesheetBuilder::__internal_rules(&format!("@keyframes {} {{}}", name));
+
+        Self {
+            name,
+            rule: rule.unchecked_into(),
+            callbacks: Callbacks::new(),
+        }
+    }
+
+    /// Adds a keyframe to the animation.
+    ///
+    /// The first argument is the keyframe selector, such as `"from"`, `"to"`,
+    /// or `"50%"`.
+    ///
+    /// The styles are added by the closure, which can use the
+    /// [`KeyframeBuilder`] methods:
+    ///
+    /// ```rust
+    /// keyframes! {
+    ///     .keyframe("from", |s| s
+    ///         .style("opacity", "0"))
+    ///     .keyframe("to", |s| s
+    ///         .style("opacity", "1"))
+    /// }
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn keyframe<B, F>(mut self, selector: B, f: F) -> Self
+    where
+        B: AsStr,
+        F: FnOnce(KeyframeBuilder) -> KeyframeBuilder,
+    {
+        let element = selector.with_str(|selector| {
+            let keyframe = format!("{} {{}}", selector);
+
+            if let Some(element) = bindings::append_keyframe(&self.rule, &keyframe) {
+                element
+            } else {
+                invalid_style(InvalidStyle::Selectors { rules: &[keyframe] });
+
+                bindings::detached_style()
+            }
+        });
+
+        let mut keyframe = f(KeyframeBuilder {
+            element,
+            callbacks: Callbacks::new(),
+        });
+
+        self.callbacks
+            .after_insert
+            .append(&mut keyframe.callbacks.after_insert);
+        self.callbacks
+            .after_remove
+            .append(&mut keyframe.callbacks.after_remove);
+
+        self
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn __internal_done(mut self) -> String {
+        self.callbacks.trigger_after_insert();
+
+        // This leaks the callbacks forever
+        self.callbacks.leak();
+
+        self.name
+    }
+}
+
+/// This is used by [`KeyframesBuilder::keyframe`].
+///
+/// Keyframes can't contain nested rules, so unlike [`StylesheetBuilder`] it
+/// only supports styles.
+// TODO better warning message for must_use
+#[must_use]
+pub struct KeyframeBuilder {
+    element: CssStyleDeclaration,
+    callbacks: Callbacks,
+}
+
+impl KeyframeBuilder {
+    #[inline]
+    #[track_caller]
+    pub fn style<B, C>(self, name: B, value: C) -> Self
+    where
+        B: MultiStr,
+        C: MultiStr,
+    {
+        set_style(&self.element, &name, value, false);
+        self
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn style_important<B, C>(self, name: B, value: C) -> Self
+    where
+        B: MultiStr,
+        C: MultiStr,
+    {
+        set_style(&self.element, &name, value, true);
+        self
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn style_signal<B, C, D, E>(mut self, name: B, value: E) -> Self
+    where
+        B: MultiStr + 'static,
+        C: MultiStr,
+        D: OptionStr<Output = C>,
+        E: Signal<Item = D> + 'static,
+    {
+        set_style_signal(
+            self.element.clone(),
+            &mut self.callbacks,
+            name,
+            value,
+            false,
+        );
+        self
+    }
+}
+
+#[doc(hidden)]
+pub mod __internal {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    pub use web_sys::{HtmlElement, SvgElement};
+
+    #[cfg(not(feature = "static-css"))]
+    use super::{in_stylesheet, StylesheetBuilder};
+    use crate::{
+        fragment::{BoxFragment, Fragment, FragmentBuilder},
+        static_css,
+        traits::MultiStr,
+    };
+
+    pub fn make_class_id(name: Option<&str>) -> String {
         // TODO replace this with a global counter in JavaScript ?
         // TODO can this be made more efficient ?
         static CLASS_ID: AtomicU32 = AtomicU32::new(0);
@@ -1889,6 +3457,43 @@ pub mod __internal {
         format!("{}_{}", name, id)
     }
 
+    /// This is used by `static_class!` so that the rule is only inserted once.
+    pub fn static_class(prefix: Option<&str>, declarations: &str) -> String {
+        let class_name = static_css::class_name(prefix, declarations);
+
+        #[cfg(not(feature = "static-css"))]
+        insert_static_rule(&static_css::class_rule(&class_name, declarations));
+
+        class_name
+    }
+
+    #[inline]
+    pub fn static_stylesheet(selector: &str, declarations: &str) {
+        #[cfg(not(feature = "static-css"))]
+        insert_static_rule(&static_css::stylesheet_rule(selector, declarations));
+
+        #[cfg(feature = "static-css")]
+        let _ = (selector, declarations);
+    }
+
+    // With the `static-css` feature the rules are in the generated `.css` file instead
+    #[cfg(not(feature = "static-css"))]
+    fn insert_static_rule(rule: &str) {
+        use std::{cell::RefCell, collections::HashSet};
+
+        thread_local! {
+            static INSERTED: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+        }
+
+        let is_new = INSERTED.with(|inserted| inserted.borrow_mut().insert(rule.to_owned()));
+
+        if is_new {
+            in_stylesheet(None, || {
+                StylesheetBuilder::__internal_stylesheet(rule).__internal_done();
+            });
+        }
+    }
+
     pub struct Pseudo<'a, A> {
         class_name: &'a str,
         pseudos: A,
@@ -1955,7 +3560,8 @@ pub mod __internal {
 mod tests {
     use futures_signals::signal::{always, SignalExt};
     use once_cell::sync::Lazy;
-    use web_sys::HtmlElement;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::{HtmlElement, ShadowRootInit};
 
     use super::{text_signal, DomBuilder, RefFn};
     use crate::{html, shadow_root, with_cfg, ShadowRootMode};
@@ -2079,6 +3685,298 @@ mod tests {
             .style_signal(["-moz-foo", "-webkit-foo", "foo"], always("bar".to_owned()).map(|x| Some(RefFn::new(x, |x| x.as_str()))));
     }
 
+    #[test]
+    fn focus_helpers() {
+        let _a = html!("div", {
+            .click_outside(|_| {})
+            .focus_trap()
+        });
+    }
+
+    #[test]
+    fn portal() {
+        let _a = html!("div", {
+            .portal(&super::body(), always(Some(html!("span"))))
+        });
+    }
+
+    #[test]
+    fn error_boundary() {
+        use futures_signals::signal_vec::always as always_vec;
+
+        let _a = html!("div", {
+            .child_signal_result(always(Ok::<_, &str>(Some(html!("span")))), |_| None)
+            .children_signal_vec_result(always_vec(vec![Err("foo")]), |e| {
+                html!("span", { .text(&e.to_string()) })
+            })
+        });
+    }
+
+    #[test]
+    fn child_future() {
+        let _a = html!("div", {
+            .child_future(html!("span"), async { html!("div") })
+            .child_future_result(html!("span"), async { Err::<_, &str>("foo") }, |_| html!("div"))
+            .children_future(html!("span"), async { vec![html!("div")] })
+        });
+    }
+
+    #[test]
+    fn scoped_class() {
+        let _a = html!("div", {
+            .scoped_class(crate::class_handle! {
+                .style("color", "green")
+                .pseudo!(":hover", {
+                    .style("color", "red")
+                })
+            })
+        });
+    }
+
+    #[test]
+    fn static_class() {
+        crate::static_stylesheet!("body", {
+            .style("margin", "0px")
+        });
+
+        let _a = html!("div", {
+            .class(crate::static_class! {
+                #![prefix = "button"]
+                .style("color", "green")
+                .style_important("z-index", 5)
+            })
+        });
+    }
+
+    #[test]
+    fn grouping_rules() {
+        let _a = html!("div", {
+            .class(crate::class! {
+                .style("width", "50%")
+                .media(["(max-width: 600px)", "screen"], |s| s
+                    .style("width", "100%")
+                    .supports("(display: grid)", |s| s
+                        .style_signal("display", always("grid"))))
+                .container("(min-width: 400px)", |s| s
+                    .style("display", "flex"))
+            })
+        });
+    }
+
+    #[test]
+    fn keyframes() {
+        let name = crate::keyframes! {
+            #![prefix = "fade"]
+            .keyframe("from", |s| s
+                .style("opacity", "0"))
+            .keyframe("50%", |s| s
+                .style_signal("opacity", always("0.5")))
+            .keyframe("to", |s| s
+                .style("opacity", "1"))
+        };
+
+        let _a = html!("div", {
+            .style("animation-name", &name)
+            .style("animation-duration", "1s")
+        });
+    }
+
+    #[test]
+    fn css_vars() {
+        use crate::traits::CssVars;
+
+        #[derive(Clone)]
+        struct Theme {
+            accent: &'static str,
+        }
+
+        impl CssVars for Theme {
+            fn each_var<F>(&self, mut f: F)
+            where
+                F: FnMut(&str, &str),
+            {
+                f("--accent", self.accent);
+            }
+        }
+
+        crate::stylesheet!(":root", {
+            .css_vars_signal(always(Theme { accent: "red" }))
+        });
+
+        let _a = html!("div", {
+            .css_var("--accent", "blue")
+            .css_var_signal("--size", always(Some("")))
+            .class(crate::class! {
+                .css_var("--accent", "green")
+                .style("color", "var(--accent)")
+            })
+        });
+    }
+
+    #[test]
+    fn scoped_styles() {
+        let _a = html!("div", {
+            .shadow_root!(ShadowRootMode::Open => {
+                .scoped_styles(|dom| {
+                    crate::stylesheet!(":host", {
+                        .style("display", "block")
+                    });
+
+                    let button = crate::class! {
+                        .style("color", "green")
+                    };
+
+                    dom.child(html!("button", {
+                        .class(&button)
+                    }))
+                })
+            })
+        });
+    }
+
+    #[wasm_bindgen_test]
+    fn scoped_styles_stylesheet() {
+        let element: HtmlElement = DomBuilder::new_html("div").element();
+        let shadow = element
+            .attach_shadow(&ShadowRootInit::new(ShadowRootMode::Open))
+            .unwrap();
+
+        // The stylesheet is reused when the shadow root is rendered again
+        for _ in 0..2 {
+            let _dom = DomBuilder::new(shadow.clone())
+                .scoped_styles(|dom| {
+                    crate::stylesheet!(":host", {
+                        .style("display", "block")
+                    });
+
+                    dom
+                })
+                .into_dom();
+        }
+
+        assert_eq!(shadow.adopted_style_sheets().length(), 1);
+    }
+
+    #[test]
+    fn web_animation() {
+        use crate::animation::{WebAnimationOptions, WebKeyframes};
+
+        let _a = html!("div", {
+            .web_animation(
+                WebKeyframes::new()
+                    .keyframe(|frame| frame.style("opacity", "0"))
+                    .keyframe(|frame| frame.style("background-color", "green")),
+                WebAnimationOptions {
+                    duration: 300.0,
+                    iterations: f64::INFINITY,
+                    ..WebAnimationOptions::default()
+                },
+                |animation| {
+                    let _ = animation.current_time_signal();
+                    let _finished = animation.finished();
+                }
+            )
+        });
+    }
+
+    #[test]
+    fn child_signal_animated() {
+        use super::TransitionOrder;
+        use futures_signals::signal::Mutable;
+
+        let page = Mutable::new(Some("home"));
+
+        let _a = html!("div", {
+            .child_signal_animated(300.0, TransitionOrder::Sequential, page.signal(), |page, animation| {
+                html!("div", {
+                    .style_signal("opacity", animation.signal().map(|t| t.into_f64().to_string()))
+                    .text(page)
+                })
+            })
+        });
+    }
+
+    #[test]
+    fn children_signal_vec_flip() {
+        use crate::animation::AnimatedSignalVec;
+        use futures_signals::signal_vec::MutableVec;
+
+        let items = MutableVec::new_with_values(vec![1, 2, 3]);
+
+        let _a = html!("ul", {
+            .children_signal_vec_flip(super::FlipOptions::default(), items.signal_vec()
+                .animated_map(500.0, |item, animation| {
+                    html!("li", {
+                        .style_signal("opacity", animation.signal().map(|t| t.into_f64().to_string()))
+                        .text(&item.to_string())
+                    })
+                }))
+        });
+    }
+
+    #[wasm_bindgen_test]
+    fn class_dedup() {
+        let a = crate::class! { #![dedup] .style("color", "green") };
+        let b = crate::class! { #![dedup] .style("color", "green") };
+        let c = crate::class! { #![dedup] .style_signal("color", always("green")) };
+        let d = crate::class! { #![dedup] #![prefix = "button"] .style("color", "green") };
+        let e = crate::class! {
+            #![dedup]
+            .style("color", "green")
+            .pseudo!(":hover", {
+                .style("color", "blue")
+            })
+        };
+        let f = crate::class! { .style("color", "green") };
+        let g = crate::class! { #![dedup] };
+        let h = crate::class! { #![dedup] };
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(a, d);
+        assert_ne!(a, e);
+        assert_ne!(a, f);
+        assert_ne!(g, h);
+    }
+
+    #[wasm_bindgen_test]
+    fn invalid_style_policy() {
+        use super::{
+            set_invalid_style_hook, set_invalid_style_policy, InvalidStyle, InvalidStylePolicy,
+            INVALID_STYLE_HOOK, INVALID_STYLE_POLICY,
+        };
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let styles = Rc::new(Cell::new(0));
+        let selectors = Rc::new(Cell::new(0));
+
+        set_invalid_style_policy(InvalidStylePolicy::Ignore);
+        set_invalid_style_hook(clone!(styles, selectors => move |error| {
+            match error {
+                InvalidStyle::Style { .. } => styles.set(styles.get() + 1),
+                InvalidStyle::Selectors { .. } => selectors.set(selectors.get() + 1),
+            }
+        }));
+
+        // This would panic with the default policy
+        let _a = html!("div", {
+            .style("not-a-style", "foo")
+            .class(crate::class! {
+                .pseudo!(":not-a-pseudo-class", {
+                    .style("color", "green")
+                })
+            })
+        });
+
+        // Restores the defaults so that the other tests are not affected
+        INVALID_STYLE_POLICY.with(|x| x.set(None));
+        INVALID_STYLE_HOOK.with(|x| *x.borrow_mut() = None);
+
+        assert_eq!(styles.get(), 1);
+        assert_eq!(selectors.get(), 1);
+    }
+
     #[test]
     fn shadow_root() {
         let _a = html!("div", {
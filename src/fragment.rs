@@ -1,4 +1,4 @@
-use std::{borrow::BorrowMut, rc::Rc, sync::Arc};
+use std::{borrow::BorrowMut, fmt, future::Future, rc::Rc, sync::Arc};
 
 use futures_signals::{signal::Signal, signal_vec::SignalVec};
 use web_sys::Node;
@@ -6,7 +6,7 @@ use web_sys::Node;
 #[cfg(doc)]
 use crate::{box_fragment, fragment};
 use crate::{
-    dom::{Dom, DomBuilder},
+    dom::{BoundaryError, Dom, DomBuilder},
     traits::*,
 };
 
@@ -148,6 +148,73 @@ impl<'a> FragmentBuilder<'a> {
     {
         Self(self.0.children_signal_vec(children))
     }
+
+    /// Inserts the child into the `target` node. This is the same as
+    /// [`DomBuilder::portal`].
+    #[inline]
+    #[track_caller]
+    pub fn portal<B>(self, target: &Node, child: B) -> Self
+    where
+        B: Signal<Item = Option<Dom>> + 'static,
+    {
+        Self(self.0.portal(target, child))
+    }
+
+    /// This is the same as [`DomBuilder::child_signal_result`].
+    #[inline]
+    #[track_caller]
+    pub fn child_signal_result<B, E, F>(self, child: B, fallback: F) -> Self
+    where
+        B: Signal<Item = Result<Option<Dom>, E>> + 'static,
+        E: fmt::Debug + 'static,
+        F: FnMut(BoundaryError<E>) -> Option<Dom> + 'static,
+    {
+        Self(self.0.child_signal_result(child, fallback))
+    }
+
+    /// This is the same as [`DomBuilder::children_signal_vec_result`].
+    #[inline]
+    #[track_caller]
+    pub fn children_signal_vec_result<B, E, F>(self, children: B, fallback: F) -> Self
+    where
+        B: SignalVec<Item = Result<Dom, E>> + 'static,
+        E: fmt::Debug + 'static,
+        F: FnMut(BoundaryError<E>) -> Dom + 'static,
+    {
+        Self(self.0.children_signal_vec_result(children, fallback))
+    }
+
+    /// This is the same as [`DomBuilder::child_future`].
+    #[inline]
+    #[track_caller]
+    pub fn child_future<B>(self, fallback: Dom, future: B) -> Self
+    where
+        B: Future<Output = Dom> + 'static,
+    {
+        Self(self.0.child_future(fallback, future))
+    }
+
+    /// This is the same as [`DomBuilder::child_future_result`].
+    #[inline]
+    #[track_caller]
+    pub fn child_future_result<B, E, F>(self, fallback: Dom, future: B, error: F) -> Self
+    where
+        B: Future<Output = Result<Dom, E>> + 'static,
+        E: fmt::Debug + 'static,
+        F: FnOnce(BoundaryError<E>) -> Dom + 'static,
+    {
+        Self(self.0.child_future_result(fallback, future, error))
+    }
+
+    /// This is the same as [`DomBuilder::children_future`].
+    #[inline]
+    #[track_caller]
+    pub fn children_future<B>(self, fallback: Dom, future: B) -> Self
+    where
+        B: Future<Output = Vec<Dom>> + 'static,
+    {
+        Self(self.0.children_future(fallback, future))
+    }
 }
 
 /// Creates a [`Fragment`] which can be inserted into a [`DomBuilder`].
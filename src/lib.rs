@@ -13,11 +13,19 @@ mod dom;
 mod fragment;
 mod operations;
 mod utils;
+#[cfg(feature = "vendor-prefixes")]
+mod vendor_prefixes;
 
 pub use dom::*;
 pub use fragment::*;
 pub use web_sys::ShadowRootMode;
 pub mod animation;
+pub mod custom_element;
 pub mod events;
+pub mod resource;
 pub mod routing;
+pub mod static_css;
 pub mod traits;
+
+#[cfg(test)]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
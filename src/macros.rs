@@ -1,5 +1,8 @@
 #[cfg(doc)]
-use crate::{ClassBuilder, Dom, DomBuilder, StylesheetBuilder};
+use crate::{
+    ClassBuilder, ClassHandle, Dom, DomBuilder, KeyframesBuilder, StylesheetBuilder,
+    StylesheetHandle,
+};
 
 #[doc(hidden)]
 #[macro_export]
@@ -437,6 +440,29 @@ macro_rules! stylesheet {
     };
 }
 
+/// The same as [`stylesheet!`] except it returns a [`StylesheetHandle`].
+///
+/// When the [`StylesheetHandle`] is discarded, the CSS rules are removed from
+/// the page:
+///
+/// ```rust
+/// let handle = stylesheet_handle!("div.foo", {
+///     .style("color", "green")
+/// });
+///
+/// // This removes the CSS rules for `div.foo`
+/// handle.discard();
+/// ```
+#[macro_export]
+macro_rules! stylesheet_handle {
+    ($rule:expr) => {
+        $crate::stylesheet_handle!($rule, {})
+    };
+    ($rule:expr, { $($methods:tt)* }) => {
+        $crate::StylesheetBuilder::__internal_handle($crate::apply_methods!($crate::StylesheetBuilder::__internal_new($rule), { $($methods)* }))
+    };
+}
+
 /// Creates a locally scoped CSS stylesheet.
 ///
 /// Normally CSS is global, which means you can accidentally create name
@@ -501,8 +527,46 @@ macro_rules! stylesheet {
 ///
 /// Or you can use `pub(crate)` so that it can only be accessed within your
 /// crate.
+///
+/// A `static` class must not be used for the first time inside of
+/// [`DomBuilder::scoped_styles`], because then its rules would only exist
+/// inside of that shadow root.
+///
+/// If you add `#![dedup]` at the start of the block, and the class only has
+/// static styles (no `style_signal`, [`pseudo!`](crate::pseudo), `.media`,
+/// `.supports`, or `.container`), and a `#![dedup]` class with the same prefix
+/// and the same styles has already been created, then it returns the existing
+/// class name instead of inserting a new rule:
+///
+/// ```rust
+/// let a = class! {
+///     #![dedup]
+///     .style("color", "green")
+/// };
+///
+/// let b = class! {
+///     #![dedup]
+///     .style("color", "green")
+/// };
+///
+/// assert_eq!(a, b);
+/// ```
+///
+/// This means that deduplicated classes are not unique: if you add more rules
+/// for one class (for example with [`stylesheet!`](crate::stylesheet)), those
+/// rules also apply to every identical class. Classes without any styles are
+/// never deduplicated.
 #[macro_export]
 macro_rules! class {
+    (#![dedup] #![prefix = $name:literal] $($methods:tt)*) => {{
+        $crate::ClassBuilder::__internal_done_dedup($crate::apply_methods!($crate::ClassBuilder::__internal_new(Some($name)), { $($methods)* }))
+    }};
+    (#![prefix = $name:literal] #![dedup] $($methods:tt)*) => {{
+        $crate::ClassBuilder::__internal_done_dedup($crate::apply_methods!($crate::ClassBuilder::__internal_new(Some($name)), { $($methods)* }))
+    }};
+    (#![dedup] $($methods:tt)*) => {{
+        $crate::ClassBuilder::__internal_done_dedup($crate::apply_methods!($crate::ClassBuilder::__internal_new(None), { $($methods)* }))
+    }};
     (#![prefix = $name:literal] $($methods:tt)*) => {{
         $crate::ClassBuilder::__internal_done($crate::apply_methods!($crate::ClassBuilder::__internal_new(Some($name)), { $($methods)* }))
     }};
@@ -511,6 +575,95 @@ macro_rules! class {
     }};
 }
 
+/// The same as [`class!`] except it returns a [`ClassHandle`].
+///
+/// When the [`ClassHandle`] is discarded, the CSS rules for the class
+/// (including any [`pseudo!`](crate::pseudo) rules) are removed from the page and the
+/// `style_signal` listeners are stopped.
+///
+/// This is useful for classes which are created for each component instance.
+/// Usually you will use it with [`DomBuilder::scoped_class`], which
+/// automatically discards the [`ClassHandle`]:
+///
+/// ```rust
+/// html!("div", {
+///     .scoped_class(class_handle! {
+///         .style_signal("width", ...)
+///     })
+/// })
+/// ```
+#[macro_export]
+macro_rules! class_handle {
+    (#![prefix = $name:literal] $($methods:tt)*) => {{
+        $crate::ClassBuilder::__internal_handle($crate::apply_methods!($crate::ClassBuilder::__internal_new(Some($name)), { $($methods)* }))
+    }};
+    ($($methods:tt)*) => {{
+        $crate::ClassBuilder::__internal_handle($crate::apply_methods!($crate::ClassBuilder::__internal_new(None), { $($methods)* }))
+    }};
+}
+
+/// The same as [`class!`] except it only accepts static `.style` and
+/// `.style_important` calls with literal arguments:
+///
+/// ```rust
+/// use once_cell::sync::Lazy;
+///
+/// static MY_CLASS: Lazy<String> = Lazy::new(|| static_class! {
+///     #![prefix = "button"]
+///     .style("color", "green")
+///     .style_important("background-color", "blue")
+/// });
+/// ```
+///
+/// Because the CSS is known at compile time, the whole class is inserted as a
+/// single rule, and the class name is derived from the CSS, so it is always the
+/// same.
+///
+/// If the `static-css` feature is enabled then nothing is inserted at runtime,
+/// instead the CSS is generated at build time, see the [`static_css`](crate::static_css)
+/// module for more details.
+///
+/// Unlike [`class!`], the styles are not checked, so invalid styles are
+/// silently ignored by the browser.
+#[macro_export]
+macro_rules! static_class {
+    (#![prefix = $name:literal] $(. $method:ident ($style:literal, $value:literal $(,)?))*) => {{
+        $crate::__internal::static_class(Some($name), concat!("", $($crate::__internal_static_style!($method, $style, $value)),*))
+    }};
+    ($(. $method:ident ($style:literal, $value:literal $(,)?))*) => {{
+        $crate::__internal::static_class(None, concat!("", $($crate::__internal_static_style!($method, $style, $value)),*))
+    }};
+}
+
+/// The same as [`stylesheet!`] except it only accepts static `.style` and
+/// `.style_important` calls with literal arguments:
+///
+/// ```rust
+/// static_stylesheet!("body", {
+///     .style("margin", "0px")
+/// });
+/// ```
+///
+/// See [`static_class!`] for more details.
+#[macro_export]
+macro_rules! static_stylesheet {
+    ($rule:literal, { $(. $method:ident ($style:literal, $value:literal $(,)?))* }) => {
+        $crate::__internal::static_stylesheet($rule, concat!("", $($crate::__internal_static_style!($method, $style, $value)),*))
+    };
+}
+
+// This must match `static_css::declaration`
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __internal_static_style {
+    (style, $style:literal, $value:literal) => {
+        concat!($style, ":", $value, ";")
+    };
+    (style_important, $style:literal, $value:literal) => {
+        concat!($style, ":", $value, "!important;")
+    };
+}
+
 /// Adds a pseudo rule to a [`class!`] stylesheet.
 ///
 /// A pseudo rule is either a [pseudo class](https://developer.mozilla.org/en-US/docs/Web/CSS/Pseudo-classes) or a [pseudo element](https://developer.mozilla.org/en-US/docs/Web/CSS/Pseudo-elements).
@@ -551,8 +704,61 @@ macro_rules! pseudo {
         $crate::pseudo!($this, $rules, {})
     };
     ($this:ident, $rules:expr, { $($methods:tt)* }) => {{
-        $crate::stylesheet!($crate::__internal::Pseudo::new($crate::ClassBuilder::__internal_class_name(&$this), $rules), { $($methods)* });
-        $this
+        let mut $this = $this;
+        let stylesheet = $crate::apply_methods!($crate::StylesheetBuilder::__internal_new($crate::__internal::Pseudo::new($crate::ClassBuilder::__internal_class_name(&mut $this), $rules)), { $($methods)* });
+        $crate::ClassBuilder::__internal_pseudo($this, stylesheet)
+    }};
+}
+
+/// Creates a CSS animation with `@keyframes`.
+///
+/// The `keyframes!` macro accepts a block of method calls. Inside of the block
+/// you can use [`KeyframesBuilder`] methods:
+///
+/// ```rust
+/// use once_cell::sync::Lazy;
+///
+/// static FADE_IN: Lazy<String> = Lazy::new(|| keyframes! {
+///     .keyframe("from", |s| s
+///         .style("opacity", "0"))
+///     .keyframe("to", |s| s
+///         .style("opacity", "1"))
+/// });
+/// ```
+///
+/// The block uses the [`apply_methods!`] macro, see the docs for
+/// [`apply_methods!`] for more details.
+///
+/// The `keyframes!` macro returns a `String`, which is a unique animation name.
+/// You can then use that animation name with a [`DomBuilder`] or [`class!`]:
+///
+/// ```rust
+/// html!("div", {
+///     .style("animation-name", &*FADE_IN)
+///     .style("animation-duration", "300ms")
+///     .event(|event: events::AnimationEnd| {
+///         if event.animation_name() == *FADE_IN {
+///             ...
+///         }
+///     })
+/// })
+/// ```
+///
+/// You can customize the animation name by using `#![prefix = "..."]`:
+///
+/// ```rust
+/// keyframes! {
+///     #![prefix = "fade_in"]
+///     ...
+/// }
+/// ```
+#[macro_export]
+macro_rules! keyframes {
+    (#![prefix = $name:literal] $($methods:tt)*) => {{
+        $crate::KeyframesBuilder::__internal_done($crate::apply_methods!($crate::KeyframesBuilder::__internal_new(Some($name)), { $($methods)* }))
+    }};
+    ($($methods:tt)*) => {{
+        $crate::KeyframesBuilder::__internal_done($crate::apply_methods!($crate::KeyframesBuilder::__internal_new(None), { $($methods)* }))
     }};
 }
 
@@ -1,4 +1,11 @@
-use std::{cell::RefCell, future::Future, iter::IntoIterator, rc::Rc};
+use std::{
+    cell::RefCell,
+    future::Future,
+    iter::IntoIterator,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
 
 use discard::{Discard, DiscardOnDrop};
 use futures_signals::{
@@ -7,12 +14,20 @@ use futures_signals::{
     signal_vec::{SignalVec, SignalVecExt, VecDiff},
     CancelableFutureHandle,
 };
-use futures_util::future::ready;
-use wasm_bindgen::UnwrapThrowExt;
+use futures_util::future::{poll_fn, ready, FutureExt};
+use js_sys::{Array, Object};
+use pin_project::pin_project;
+use wasm_bindgen::{JsCast, JsValue, UnwrapThrowExt};
 use wasm_bindgen_futures::spawn_local;
-use web_sys::Node;
-
-use crate::{bindings, callbacks::Callbacks, dom::Dom};
+use web_sys::{Animation, DomRect, Element, Node};
+
+use crate::{
+    animation::{reduced_motion_scale, AnimatedMapBroadcaster, MutableAnimation, Percentage},
+    bindings,
+    callbacks::Callbacks,
+    dom::{Dom, FlipOptions, TransitionOrder},
+    utils::report_js,
+};
 
 #[inline]
 pub(crate) fn spawn_future<F>(future: F) -> DiscardOnDrop<CancelableFutureHandle>
@@ -51,6 +66,158 @@ where
     })))
 }
 
+// Calls `callback` with all of the changes which are ready at the same time
+fn for_each_vec_batch<A, B>(signal: A, mut callback: B) -> CancelableFutureHandle
+where
+    A: SignalVec + 'static,
+    B: FnMut(Vec<VecDiff<A::Item>>) + 'static,
+{
+    let mut signal = Box::pin(signal);
+
+    DiscardOnDrop::leak(spawn_future(poll_fn(move |cx| {
+        let mut changes = vec![];
+
+        let poll = loop {
+            match signal.as_mut().poll_vec_change(cx) {
+                Poll::Ready(Some(change)) => changes.push(change),
+                Poll::Ready(None) => break Poll::Ready(()),
+                Poll::Pending => break Poll::Pending,
+            }
+        };
+
+        if !changes.is_empty() {
+            callback(changes);
+        }
+
+        poll
+    })))
+}
+
+// Converts panics which happen while polling the signal into `Err`
+//
+// Panics can only be caught if the target supports unwinding. With `panic = "abort"`
+// (which is always the case on wasm32) this does nothing and the panic aborts the program.
+#[pin_project]
+pub(crate) struct CatchPanic<A> {
+    #[pin]
+    signal: Option<A>,
+}
+
+impl<A> CatchPanic<A> {
+    #[inline]
+    pub(crate) fn new(signal: A) -> Self {
+        Self {
+            signal: Some(signal),
+        }
+    }
+}
+
+#[cfg(panic = "unwind")]
+#[inline]
+fn catch_panic<A, F>(f: F) -> Result<A, String>
+where
+    F: FnOnce() -> A,
+{
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f))
+        .map_err(|payload| crate::utils::panic_message(&*payload))
+}
+
+#[cfg(not(panic = "unwind"))]
+#[inline]
+fn catch_panic<A, F>(f: F) -> Result<A, String>
+where
+    F: FnOnce() -> A,
+{
+    Ok(f())
+}
+
+impl<A> Signal for CatchPanic<A>
+where
+    A: Signal,
+{
+    type Item = Result<A::Item, String>;
+
+    fn poll_change(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        let result = match this.signal.as_mut().as_pin_mut() {
+            Some(signal) => catch_panic(|| signal.poll_change(cx)),
+            None => return Poll::Ready(None),
+        };
+
+        match result {
+            Ok(poll) => poll.map(|value| value.map(Ok)),
+            Err(message) => {
+                // The signal might be in an invalid state, so it is not polled again
+                this.signal.set(None);
+                Poll::Ready(Some(Err(message)))
+            }
+        }
+    }
+}
+
+impl<A> SignalVec for CatchPanic<A>
+where
+    A: SignalVec,
+{
+    type Item = Result<A::Item, String>;
+
+    fn poll_vec_change(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<VecDiff<Self::Item>>> {
+        let mut this = self.project();
+
+        let result = match this.signal.as_mut().as_pin_mut() {
+            Some(signal) => catch_panic(|| signal.poll_vec_change(cx)),
+            None => return Poll::Ready(None),
+        };
+
+        match result {
+            Ok(poll) => poll.map(|change| change.map(|change| map_vec_diff(change, Ok))),
+            Err(message) => {
+                // The signal might be in an invalid state, so it is not polled again
+                this.signal.set(None);
+
+                Poll::Ready(Some(VecDiff::Replace {
+                    values: vec![Err(message)],
+                }))
+            }
+        }
+    }
+}
+
+// TODO use VecDiff::map if it becomes public
+fn map_vec_diff<A, B, F>(change: VecDiff<A>, mut f: F) -> VecDiff<B>
+where
+    F: FnMut(A) -> B,
+{
+    match change {
+        VecDiff::Replace { values } => VecDiff::Replace {
+            values: values.into_iter().map(f).collect(),
+        },
+        VecDiff::InsertAt { index, value } => VecDiff::InsertAt {
+            index,
+            value: f(value),
+        },
+        VecDiff::UpdateAt { index, value } => VecDiff::UpdateAt {
+            index,
+            value: f(value),
+        },
+        VecDiff::Push { value } => VecDiff::Push { value: f(value) },
+        VecDiff::RemoveAt { index } => VecDiff::RemoveAt { index },
+        VecDiff::Move {
+            old_index,
+            new_index,
+        } => VecDiff::Move {
+            old_index,
+            new_index,
+        },
+        VecDiff::Pop {} => VecDiff::Pop {},
+        VecDiff::Clear {} => VecDiff::Clear {},
+    }
+}
+
 pub(crate) fn insert_children_one(element: &Node, callbacks: &mut Callbacks, dom: &mut Dom) {
     // TODO can this be made more efficient ?
     callbacks
@@ -85,6 +252,27 @@ fn after_insert(is_inserted: bool, callbacks: &mut Callbacks) {
 
 #[inline]
 pub(crate) fn insert_child_signal<A>(element: Node, callbacks: &mut Callbacks, signal: A)
+where
+    A: Signal<Item = Option<Dom>> + 'static,
+{
+    child_signal(element, callbacks, signal, false);
+}
+
+// Inserts the child into `target` rather than into the element which owns `callbacks`.
+//
+// The child is only inserted after the owner is inserted, and it is removed from
+// `target` when the owner is removed.
+#[inline]
+pub(crate) fn insert_portal_signal<A>(target: Node, callbacks: &mut Callbacks, signal: A)
+where
+    A: Signal<Item = Option<Dom>> + 'static,
+{
+    callbacks.after_insert(move |callbacks| {
+        child_signal(target, callbacks, signal, true);
+    });
+}
+
+fn child_signal<A>(element: Node, callbacks: &mut Callbacks, signal: A, is_portal: bool)
 where
     A: Signal<Item = Option<Dom>> + 'static,
 {
@@ -94,9 +282,9 @@ where
     }
 
     impl State {
-        fn new() -> Rc<RefCell<Self>> {
+        fn new(is_inserted: bool) -> Rc<RefCell<Self>> {
             Rc::new(RefCell::new(State {
-                is_inserted: false,
+                is_inserted,
                 child: None,
             }))
         }
@@ -132,8 +320,13 @@ where
             }
         }
 
-        fn on_remove(&mut self) {
+        fn on_remove(&mut self, portal: Option<&Node>) {
             if let Some(old_child) = self.child.take() {
+                // Portals are not inside of the owner, so they must be removed manually
+                if let Some(element) = portal {
+                    bindings::remove_child(element, &old_child.element);
+                }
+
                 old_child.callbacks.discard();
             }
         }
@@ -142,13 +335,23 @@ where
     struct OnRemove {
         state: Rc<RefCell<State>>,
         signal: CancelableFutureHandle,
+        portal: Option<(Node, Node)>,
     }
 
     impl Discard for OnRemove {
         #[inline]
         fn discard(self) {
             self.signal.discard();
-            self.state.borrow_mut().on_remove();
+
+            match self.portal {
+                Some((element, marker)) => {
+                    self.state.borrow_mut().on_remove(Some(&element));
+                    bindings::remove_child(&element, &marker);
+                }
+                None => {
+                    self.state.borrow_mut().on_remove(None);
+                }
+            }
         }
     }
 
@@ -157,9 +360,18 @@ where
 
     bindings::append_child(&element, &marker);
 
-    let state = State::new();
+    let portal = if is_portal {
+        Some((element.clone(), marker.clone()))
+    } else {
+        None
+    };
 
-    State::after_insert(state.clone(), callbacks);
+    // Portals are created after the owner is inserted
+    let state = State::new(is_portal);
+
+    if !is_portal {
+        State::after_insert(state.clone(), callbacks);
+    }
 
     callbacks.after_remove(OnRemove {
         state: state.clone(),
@@ -167,12 +379,215 @@ where
             let mut state = state.borrow_mut();
             state.after_remove(&element, &marker, child);
         }),
+        portal,
+    });
+}
+
+pub(crate) fn insert_child_signal_animated<A, B, F>(
+    element: Node,
+    callbacks: &mut Callbacks,
+    signal: A,
+    duration: f64,
+    order: TransitionOrder,
+    mut f: F,
+) where
+    A: Signal<Item = Option<B>> + 'static,
+    F: FnMut(B, AnimatedMapBroadcaster) -> Dom + 'static,
+{
+    struct Child {
+        dom: Dom,
+        animation: MutableAnimation,
+    }
+
+    struct Leaving {
+        id: usize,
+        dom: Dom,
+        signal: CancelableFutureHandle,
+    }
+
+    struct State {
+        element: Node,
+        marker: Node,
+        is_inserted: bool,
+        order: TransitionOrder,
+        child: Option<Child>,
+        // The children which are playing their exit animation
+        leaving: Vec<Leaving>,
+        // With `TransitionOrder::Sequential` the new child waits until the old children have left
+        waiting: Option<Child>,
+        next_id: usize,
+    }
+
+    impl State {
+        fn after_insert(state: Rc<RefCell<Self>>, callbacks: &mut Callbacks) {
+            callbacks.after_insert(move |_| {
+                let mut state = state.borrow_mut();
+
+                if !state.is_inserted {
+                    state.is_inserted = true;
+
+                    if let Some(child) = &mut state.child {
+                        child.dom.callbacks.trigger_after_insert();
+                    }
+
+                    for leaving in state.leaving.iter_mut() {
+                        leaving.dom.callbacks.trigger_after_insert();
+                    }
+                }
+            });
+        }
+
+        fn enter(&mut self, mut child: Child) {
+            bindings::insert_child_before(&self.element, &child.dom.element, &self.marker);
+
+            after_insert(self.is_inserted, &mut child.dom.callbacks);
+
+            child.animation.animate_to(Percentage::END);
+
+            self.child = Some(child);
+        }
+
+        fn leave(state: &Rc<RefCell<Self>>, lock: &mut Self, child: Child) {
+            // There is nothing to animate if it isn't in the DOM
+            if !lock.is_inserted {
+                bindings::remove_child(&lock.element, &child.dom.element);
+                child.dom.callbacks.discard();
+                return;
+            }
+
+            let id = lock.next_id;
+            lock.next_id += 1;
+
+            child.animation.animate_to(Percentage::START);
+
+            let signal = {
+                let state = state.clone();
+
+                DiscardOnDrop::leak(spawn_future(
+                    child
+                        .animation
+                        .signal()
+                        .wait_for(Percentage::START)
+                        .map(move |_| {
+                            let mut lock = state.borrow_mut();
+                            lock.left(id);
+                        }),
+                ))
+            };
+
+            lock.leaving.push(Leaving {
+                id,
+                dom: child.dom,
+                signal,
+            });
+        }
+
+        fn left(&mut self, id: usize) {
+            if let Some(index) = self.leaving.iter().position(|leaving| leaving.id == id) {
+                let leaving = self.leaving.remove(index);
+                bindings::remove_child(&self.element, &leaving.dom.element);
+                leaving.dom.callbacks.discard();
+            }
+
+            if self.leaving.is_empty() {
+                if let Some(child) = self.waiting.take() {
+                    self.enter(child);
+                }
+            }
+        }
+
+        fn change(state: &Rc<RefCell<Self>>, new_child: Option<Child>) {
+            let mut lock = state.borrow_mut();
+
+            // The waiting child was never displayed, so it is replaced immediately
+            if let Some(child) = lock.waiting.take() {
+                child.dom.callbacks.discard();
+            }
+
+            if let Some(child) = lock.child.take() {
+                Self::leave(state, &mut lock, child);
+            }
+
+            if let Some(child) = new_child {
+                if lock.order == TransitionOrder::Sequential && !lock.leaving.is_empty() {
+                    lock.waiting = Some(child);
+                } else {
+                    lock.enter(child);
+                }
+            }
+        }
+
+        fn on_remove(&mut self) {
+            if let Some(child) = self.child.take() {
+                child.dom.callbacks.discard();
+            }
+
+            if let Some(child) = self.waiting.take() {
+                child.dom.callbacks.discard();
+            }
+
+            for leaving in self.leaving.drain(..) {
+                leaving.signal.discard();
+                leaving.dom.callbacks.discard();
+            }
+        }
+    }
+
+    struct OnRemove {
+        state: Rc<RefCell<State>>,
+        signal: CancelableFutureHandle,
+    }
+
+    impl Discard for OnRemove {
+        #[inline]
+        fn discard(self) {
+            self.signal.discard();
+            self.state.borrow_mut().on_remove();
+        }
+    }
+
+    // TODO replace with https://github.com/whatwg/dom/issues/736
+    let marker = bindings::create_empty_node();
+
+    bindings::append_child(&element, &marker);
+
+    let state = Rc::new(RefCell::new(State {
+        element,
+        marker,
+        is_inserted: false,
+        order,
+        child: None,
+        leaving: vec![],
+        waiting: None,
+        next_id: 0,
+    }));
+
+    State::after_insert(state.clone(), callbacks);
+
+    callbacks.after_remove(OnRemove {
+        state: state.clone(),
+        signal: for_each(signal, move |value| {
+            let child = value.map(|value| {
+                let animation = MutableAnimation::new(duration);
+
+                Child {
+                    dom: f(value, AnimatedMapBroadcaster(animation.clone())),
+                    animation,
+                }
+            });
+
+            State::change(&state, child);
+        }),
     });
 }
 
 #[inline]
-pub(crate) fn insert_children_signal_vec<A>(element: Node, callbacks: &mut Callbacks, signal: A)
-where
+pub(crate) fn insert_children_signal_vec<A>(
+    element: Node,
+    callbacks: &mut Callbacks,
+    signal: A,
+    flip: Option<FlipOptions>,
+) where
     A: SignalVec<Item = Dom> + 'static,
 {
     struct State {
@@ -180,6 +595,8 @@ where
         marker: Node,
         is_inserted: bool,
         children: Vec<Dom>,
+        // The FLIP animations which were started by the previous batch of changes
+        animations: Vec<Animation>,
     }
 
     impl State {
@@ -189,6 +606,7 @@ where
                 marker,
                 is_inserted: false,
                 children: vec![],
+                animations: vec![],
             }))
         }
 
@@ -227,6 +645,74 @@ where
             }
         }
 
+        // Returns the position of every child element, this is used for FLIP animations
+        fn positions(&mut self) -> Vec<(Element, DomRect)> {
+            // The previous animations are cancelled so that it measures the real positions,
+            // otherwise the children would jump if they are moved again while animating
+            for animation in self.animations.drain(..) {
+                animation.cancel();
+            }
+
+            if self.is_inserted {
+                self.children
+                    .iter()
+                    .filter_map(|dom| dom.element.dyn_ref::<Element>())
+                    .map(|element| (element.clone(), element.get_bounding_client_rect()))
+                    .collect()
+            } else {
+                vec![]
+            }
+        }
+
+        // Animates the children from their old position to their new position
+        fn flip(&mut self, options: &FlipOptions, positions: Vec<(Element, DomRect)>) {
+            let duration = if options.essential {
+                options.duration
+            } else {
+                options.duration * reduced_motion_scale()
+            };
+
+            if duration <= 0.0 {
+                return;
+            }
+
+            for (element, first) in positions {
+                // Children which were removed are not animated
+                if element.parent_node().as_ref() != Some(&self.element) {
+                    continue;
+                }
+
+                let last = element.get_bounding_client_rect();
+                let x = first.left() - last.left();
+                let y = first.top() - last.top();
+
+                if x != 0.0 || y != 0.0 {
+                    let from = Object::new();
+                    let to = Object::new();
+                    bindings::set_property(
+                        &from,
+                        "transform",
+                        &JsValue::from(format!("translate({}px, {}px)", x, y)),
+                    );
+                    bindings::set_property(&to, "transform", &JsValue::from("none"));
+
+                    let timing = Object::new();
+                    bindings::set_property(&timing, "duration", &JsValue::from(duration));
+                    bindings::set_property(&timing, "easing", &JsValue::from(&options.easing));
+
+                    // The same as `web_animation`, the error is reported and the children move
+                    // without animating
+                    match bindings::animate(&element, &Array::of2(&from, &to), &timing) {
+                        Ok(animation) => self.animations.push(animation),
+                        Err(e) => {
+                            report_js("Invalid FLIP animation", &e);
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+
         // TODO verify that this will drop `children`
         fn process_change(&mut self, change: VecDiff<Dom>) {
             match change {
@@ -335,9 +821,23 @@ where
 
     callbacks.after_remove(OnRemove {
         state: state.clone(),
-        signal: for_each_vec(signal, move |change| {
-            let mut state = state.borrow_mut();
-            state.process_change(change);
-        }),
+        signal: match flip {
+            None => for_each_vec(signal, move |change| {
+                let mut state = state.borrow_mut();
+                state.process_change(change);
+            }),
+            // The positions are measured before and after the whole batch of changes
+            Some(options) => for_each_vec_batch(signal, move |changes| {
+                let mut state = state.borrow_mut();
+
+                let positions = state.positions();
+
+                for change in changes {
+                    state.process_change(change);
+                }
+
+                state.flip(&options, positions);
+            }),
+        },
     });
 }
@@ -0,0 +1,394 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+    future::Future,
+    hash::Hash,
+    pin::Pin,
+    rc::{Rc, Weak},
+};
+
+use discard::DiscardOnDrop;
+use futures_signals::{
+    signal::{Mutable, Signal, SignalExt},
+    CancelableFutureHandle,
+};
+use futures_util::future::ready;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{AbortController, AbortSignal, RequestInit, Response};
+
+use crate::{bindings::WINDOW, operations::spawn_future, utils::UnwrapJsExt};
+
+/// The current state of a [`Resource`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadState<T, E = JsValue> {
+    /// Nothing has been loaded yet.
+    Idle,
+    /// The value is currently being loaded.
+    Loading,
+    /// The value was successfully loaded.
+    Ready(T),
+    /// The value failed to load.
+    Error(E),
+}
+
+impl<T, E> LoadState<T, E> {
+    #[inline]
+    pub fn is_loading(&self) -> bool {
+        matches!(self, LoadState::Loading)
+    }
+
+    #[inline]
+    pub fn ready(&self) -> Option<&T> {
+        match self {
+            LoadState::Ready(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+type Fetcher<K, T, E> = Box<dyn Fn(&K, AbortSignal) -> Pin<Box<dyn Future<Output = Result<T, E>>>>>;
+
+struct Loading {
+    abort: AbortController,
+    _future: DiscardOnDrop<CancelableFutureHandle>,
+}
+
+impl Drop for Loading {
+    #[inline]
+    fn drop(&mut self) {
+        self.abort.abort();
+    }
+}
+
+struct ResourceInner<K, T, E> {
+    fetcher: Fetcher<K, T, E>,
+    state: Mutable<LoadState<T, E>>,
+    key: RefCell<Option<K>>,
+    cache: RefCell<HashMap<K, T>>,
+    loading: RefCell<Option<Loading>>,
+    id: Cell<usize>,
+}
+
+/// Asynchronously loads values, with caching and cancellation.
+///
+/// The `fetcher` is called with the key and an [`AbortSignal`], and it returns
+/// a `Future` which loads the value. Because the `fetcher` is an ordinary
+/// closure it can use any transport, including a mock in tests:
+///
+/// ```rust
+/// let users = Resource::new(|id: &u32, signal| {
+///     let url = format!("/api/users/{}", id);
+///     async move { fetch_text(&url, &signal).await }
+/// });
+///
+/// html!("div", {
+///     // Loads a new user whenever `state.user_id` changes, and cancels the
+///     // request when the <div> is removed
+///     .future(users.load_signal(state.user_id.signal()))
+///
+///     .child_signal(users.state().map(|state| {
+///         match state {
+///             LoadState::Ready(user) => Some(html!("span", { .text(&user) })),
+///             _ => None,
+///         }
+///     }))
+/// })
+/// ```
+///
+/// Successfully loaded values are cached by their key, so loading the same key
+/// again will not call the `fetcher`. Use [`refetch`](Resource::refetch) or
+/// [`invalidate`](Resource::invalidate) to bypass the cache.
+///
+/// The error type `E` is whatever the `fetcher` returns, it defaults to
+/// [`JsValue`] which is used by [`fetch_text`].
+pub struct Resource<K, T, E = JsValue> {
+    inner: Rc<ResourceInner<K, T, E>>,
+}
+
+impl<K, T, E> Clone for Resource<K, T, E> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K, T, E> fmt::Debug for Resource<K, T, E>
+where
+    K: fmt::Debug,
+    T: fmt::Debug,
+    E: fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Resource")
+            .field("key", &self.inner.key.borrow())
+            .field("state", &self.inner.state)
+            .finish()
+    }
+}
+
+impl<K, T, E> Resource<K, T, E>
+where
+    K: Clone + Eq + Hash + 'static,
+    T: Clone + 'static,
+    E: Clone + 'static,
+{
+    pub fn new<F, A>(fetcher: F) -> Self
+    where
+        F: Fn(&K, AbortSignal) -> A + 'static,
+        A: Future<Output = Result<T, E>> + 'static,
+    {
+        Self {
+            inner: Rc::new(ResourceInner {
+                fetcher: Box::new(move |key, signal| Box::pin(fetcher(key, signal))),
+                state: Mutable::new(LoadState::Idle),
+                key: RefCell::new(None),
+                cache: RefCell::new(HashMap::new()),
+                loading: RefCell::new(None),
+                id: Cell::new(0),
+            }),
+        }
+    }
+
+    /// Returns a `Signal` of the current [`LoadState`].
+    #[inline]
+    pub fn state(&self) -> impl Signal<Item = LoadState<T, E>> {
+        self.inner.state.signal_cloned()
+    }
+
+    /// Loads the value for `key`, cancelling any request which is in progress.
+    ///
+    /// If the value is already cached then it is used immediately. If `key` is
+    /// already being loaded then it does nothing.
+    pub fn load(&self, key: K) {
+        // This happens when `load_signal` receives the same key again
+        if self.inner.loading.borrow().is_some() && self.inner.key.borrow().as_ref() == Some(&key) {
+            return;
+        }
+
+        let cached = self.inner.cache.borrow().get(&key).cloned();
+
+        match cached {
+            Some(value) => {
+                self.cancel_loading();
+                *self.inner.key.borrow_mut() = Some(key);
+                self.inner.state.set(LoadState::Ready(value));
+            }
+            None => {
+                self.start(key);
+            }
+        }
+    }
+
+    /// Loads the current key again, ignoring the cache.
+    pub fn refetch(&self) {
+        let key = self.inner.key.borrow().clone();
+
+        if let Some(key) = key {
+            self.start(key);
+        }
+    }
+
+    /// Returns a `Future` which calls [`load`](Resource::load) every time the
+    /// `keys` signal changes.
+    ///
+    /// When the `Future` is cancelled (for example by [`DomBuilder::future`](crate::DomBuilder::future)
+    /// when the [`Dom`](crate::Dom) is removed) the request is also cancelled.
+    pub fn load_signal<S>(&self, keys: S) -> impl Future<Output = ()>
+    where
+        S: Signal<Item = K>,
+    {
+        struct CancelOnDrop<K, T, E>(Resource<K, T, E>)
+        where
+            K: Clone + Eq + Hash + 'static,
+            T: Clone + 'static,
+            E: Clone + 'static;
+
+        impl<K, T, E> Drop for CancelOnDrop<K, T, E>
+        where
+            K: Clone + Eq + Hash + 'static,
+            T: Clone + 'static,
+            E: Clone + 'static,
+        {
+            #[inline]
+            fn drop(&mut self) {
+                self.0.cancel();
+            }
+        }
+
+        let resource = CancelOnDrop(self.clone());
+
+        keys.for_each(move |key| {
+            resource.0.load(key);
+            ready(())
+        })
+    }
+
+    /// Cancels the request which is in progress (if any).
+    ///
+    /// If a request was cancelled then the state is set to [`LoadState::Idle`].
+    pub fn cancel(&self) {
+        if self.cancel_loading() {
+            self.inner.state.set(LoadState::Idle);
+        }
+    }
+
+    /// Removes the cached value for `key`.
+    pub fn invalidate(&self, key: &K) {
+        self.inner.cache.borrow_mut().remove(key);
+    }
+
+    /// Removes all of the cached values.
+    pub fn clear_cache(&self) {
+        self.inner.cache.borrow_mut().clear();
+    }
+
+    fn cancel_loading(&self) -> bool {
+        // This invalidates the id of the current request
+        self.inner.id.set(self.inner.id.get().wrapping_add(1));
+
+        let loading = self.inner.loading.borrow_mut().take();
+        loading.is_some()
+    }
+
+    fn start(&self, key: K) {
+        self.cancel_loading();
+
+        let id = self.inner.id.get();
+        let abort = AbortController::new().unwrap_js();
+        let future = (self.inner.fetcher)(&key, abort.signal());
+
+        *self.inner.key.borrow_mut() = Some(key.clone());
+        self.inner.state.set(LoadState::Loading);
+
+        // This uses a Weak to avoid a reference cycle with `loading`
+        let inner: Weak<ResourceInner<K, T, E>> = Rc::downgrade(&self.inner);
+
+        let handle = spawn_future(async move {
+            let result = future.await;
+
+            if let Some(inner) = inner.upgrade() {
+                // If it hasn't been replaced with a new request...
+                if inner.id.get() == id {
+                    inner.loading.borrow_mut().take();
+
+                    match result {
+                        Ok(value) => {
+                            inner.cache.borrow_mut().insert(key, value.clone());
+                            inner.state.set(LoadState::Ready(value));
+                        }
+                        Err(e) => {
+                            inner.state.set(LoadState::Error(e));
+                        }
+                    }
+                }
+            }
+        });
+
+        *self.inner.loading.borrow_mut() = Some(Loading {
+            abort,
+            _future: handle,
+        });
+    }
+}
+
+/// Fetches the `url` and returns the response body as a `String`.
+///
+/// This is a simple transport which can be used with [`Resource::new`].
+pub async fn fetch_text(url: &str, signal: &AbortSignal) -> Result<String, JsValue> {
+    let init = RequestInit::new();
+    init.set_signal(Some(signal));
+
+    let future = WINDOW.with(|window| window.fetch_with_str_and_init(url, &init));
+
+    let response: Response = JsFuture::from(future).await?.unchecked_into();
+
+    if !response.ok() {
+        return Err(
+            js_sys::Error::new(&format!("Fetch failed with status {}", response.status())).into(),
+        );
+    }
+
+    let text = JsFuture::from(response.text()?).await?;
+
+    Ok(text.as_string().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use futures_signals::signal::SignalExt;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::{LoadState, Resource};
+
+    fn current(resource: &Resource<u32, String, &'static str>) -> LoadState<String, &'static str> {
+        resource.inner.state.get_cloned()
+    }
+
+    #[wasm_bindgen_test]
+    async fn mock_transport() {
+        let calls = Rc::new(Cell::new(0));
+
+        let resource = Resource::new({
+            let calls = calls.clone();
+
+            move |key: &u32, _signal| {
+                calls.set(calls.get() + 1);
+
+                let key = *key;
+
+                async move {
+                    if key == 0 {
+                        Err("not found")
+                    } else {
+                        Ok(format!("user {}", key))
+                    }
+                }
+            }
+        });
+
+        assert_eq!(current(&resource), LoadState::Idle);
+
+        resource.load(1);
+        assert_eq!(current(&resource), LoadState::Loading);
+
+        // Loading the same key again doesn't restart the request
+        resource.load(1);
+        assert_eq!(calls.get(), 1);
+
+        resource
+            .state()
+            .wait_for(LoadState::Ready("user 1".to_owned()))
+            .await;
+        assert_eq!(calls.get(), 1);
+
+        // The second load uses the cache
+        resource.load(1);
+        assert_eq!(current(&resource), LoadState::Ready("user 1".to_owned()));
+        assert_eq!(calls.get(), 1);
+
+        // Refetching ignores the cache
+        resource.refetch();
+        assert_eq!(current(&resource), LoadState::Loading);
+        assert_eq!(calls.get(), 2);
+
+        resource
+            .state()
+            .wait_for(LoadState::Ready("user 1".to_owned()))
+            .await;
+
+        resource.load(0);
+        assert_eq!(current(&resource), LoadState::Loading);
+
+        resource
+            .state()
+            .wait_for(LoadState::Error("not found"))
+            .await;
+        assert_eq!(calls.get(), 3);
+    }
+}
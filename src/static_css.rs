@@ -0,0 +1,710 @@
+//! Build time extraction of static CSS.
+//!
+//! The [`static_class!`](crate::static_class) and [`static_stylesheet!`](crate::static_stylesheet)
+//! macros only accept static `.style(...)` calls, so their CSS is known at
+//! compile time.
+//!
+//! Normally that CSS is still inserted at runtime (with a single rule per
+//! class), but if the `static-css` feature is enabled then nothing is inserted,
+//! and instead you use [`write()`] in your `build.rs` to generate a `.css` file:
+//!
+//! ```rust,no_run
+//! // build.rs
+//! println!("cargo:rerun-if-changed=src");
+//! dominator::static_css::write("src", "static/dominator.css").unwrap();
+//! ```
+//!
+//! Then you include the `.css` file in your HTML with a `<link>` tag.
+//!
+//! If a macro call can't be extracted then [`write()`] returns an error, so
+//! the build fails instead of generating a class without any styles.
+//!
+//! Classes which use signals or other dynamic styles should keep using
+//! [`class!`](crate::class), which always uses the runtime path.
+
+use std::{
+    collections::HashSet,
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+    str::Chars,
+};
+
+// FNV-1a, because it is simple and stable across Rust versions
+const fn hash(css: &str) -> u64 {
+    let bytes = css.as_bytes();
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+        i += 1;
+    }
+
+    hash
+}
+
+/// Returns the class name for a [`static_class!`](crate::static_class).
+///
+/// The class name only depends on the `prefix` and the CSS declarations, so
+/// it is the same at runtime and in the generated `.css` file.
+pub fn class_name(prefix: Option<&str>, declarations: &str) -> String {
+    format!("{}_{:x}", prefix.unwrap_or("__static"), hash(declarations))
+}
+
+#[inline]
+pub(crate) fn class_rule(class_name: &str, declarations: &str) -> String {
+    format!(".{}{{{}}}", class_name, declarations)
+}
+
+#[inline]
+pub(crate) fn stylesheet_rule(selector: &str, declarations: &str) -> String {
+    format!("{}{{{}}}", selector, declarations)
+}
+
+// This must match the `__internal_static_style!` macro
+fn declaration(method: &str, name: &str, value: &str) -> Option<String> {
+    match method {
+        "style" => Some(format!("{}:{};", name, value)),
+        "style_important" => Some(format!("{}:{}!important;", name, value)),
+        _ => None,
+    }
+}
+
+fn ident_len(input: &str) -> usize {
+    input
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(input.len())
+}
+
+// Block comments can be nested
+fn block_comment_len(input: &str) -> usize {
+    let mut depth = 0;
+    let mut pos = 0;
+
+    while pos < input.len() {
+        let rest = &input[pos..];
+
+        if rest.starts_with("/*") {
+            depth += 1;
+            pos += 2;
+        } else if rest.starts_with("*/") {
+            depth -= 1;
+            pos += 2;
+
+            if depth == 0 {
+                break;
+            }
+        } else {
+            pos += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    pos
+}
+
+// Integers are normalized by `concat!` (`0x10u8` becomes `16`), but floats
+// keep their text without the suffix (`1.50f32` becomes `1.50`)
+fn number(input: &str) -> Option<(usize, String)> {
+    let bytes = input.as_bytes();
+
+    let radix = match input.get(..2) {
+        Some("0x") => 16,
+        Some("0o") => 8,
+        Some("0b") => 2,
+        _ => 10,
+    };
+
+    let is_digit =
+        |pos: usize| matches!(bytes.get(pos), Some(&b) if b == b'_' || (b as char).is_digit(radix));
+
+    let start = if radix == 10 { 0 } else { 2 };
+    let mut len = start;
+    let mut is_float = false;
+
+    while is_digit(len) {
+        len += 1;
+    }
+
+    if radix == 10 {
+        // `1.` is a float, but `1..2` and `1.foo()` are not
+        if bytes.get(len) == Some(&b'.')
+            && !matches!(bytes.get(len + 1), Some(&b) if b == b'.' || b == b'_' || b.is_ascii_alphabetic())
+        {
+            is_float = true;
+            len += 1;
+
+            while is_digit(len) {
+                len += 1;
+            }
+        }
+
+        if matches!(bytes.get(len), Some(b'e') | Some(b'E')) {
+            let mut end = len + 1;
+
+            if matches!(bytes.get(end), Some(b'+') | Some(b'-')) {
+                end += 1;
+            }
+
+            let digits = end;
+
+            while is_digit(end) {
+                end += 1;
+            }
+
+            if input[digits..end].contains(|c: char| c.is_ascii_digit()) {
+                is_float = true;
+                len = end;
+            }
+        }
+    }
+
+    let suffix = &input[len..len + ident_len(&input[len..])];
+
+    let value = match suffix {
+        "f32" | "f64" if radix == 10 => input[..len].replace('_', ""),
+        "" if is_float => input[..len].replace('_', ""),
+        "" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64"
+        | "i128" | "isize"
+            if !is_float =>
+        {
+            u128::from_str_radix(&input[start..len].replace('_', ""), radix)
+                .ok()?
+                .to_string()
+        }
+        _ => return None,
+    };
+
+    Some((len + suffix.len(), value))
+}
+
+// Parses the escape after a `\`
+fn escape(chars: &mut Chars) -> Option<char> {
+    match chars.next()? {
+        'n' => Some('\n'),
+        'r' => Some('\r'),
+        't' => Some('\t'),
+        '0' => Some('\0'),
+        '\\' => Some('\\'),
+        '"' => Some('"'),
+        '\'' => Some('\''),
+        'x' => {
+            let rest = chars.as_str();
+            let code = u8::from_str_radix(rest.get(..2)?, 16).ok()?;
+            chars.nth(1);
+            Some(char::from(code))
+        }
+        'u' => {
+            let rest = chars.as_str().strip_prefix('{')?;
+            let end = rest.find('}')?;
+            let code = u32::from_str_radix(&rest[..end].replace('_', ""), 16).ok()?;
+
+            for _ in 0..end + 2 {
+                chars.next();
+            }
+
+            char::from_u32(code)
+        }
+        _ => None,
+    }
+}
+
+fn string(input: &str) -> Option<(usize, String)> {
+    let mut chars = input.strip_prefix('"')?.chars();
+    let mut output = String::new();
+
+    loop {
+        match chars.next()? {
+            '"' => return Some((input.len() - chars.as_str().len(), output)),
+            '\\' => {
+                let rest = chars.as_str();
+
+                // A `\` at the end of the line skips the whitespace at the start of the next line
+                if rest.starts_with('\n') {
+                    let skip = rest.len() - rest.trim_start().len();
+                    chars = rest[skip..].chars();
+                } else {
+                    output.push(escape(&mut chars)?);
+                }
+            }
+            c => output.push(c),
+        }
+    }
+}
+
+fn raw_string(input: &str) -> Option<(usize, String)> {
+    let rest = input.strip_prefix('r')?;
+    let hashes = rest.len() - rest.trim_start_matches('#').len();
+    let rest = rest[hashes..].strip_prefix('"')?;
+
+    let end = format!("\"{}", "#".repeat(hashes));
+    let len = rest.find(&end)?;
+
+    Some((1 + hashes + 1 + len + end.len(), rest[..len].to_owned()))
+}
+
+fn char_literal(input: &str) -> Option<(usize, String)> {
+    let mut chars = input.strip_prefix('\'')?.chars();
+
+    let c = match chars.next()? {
+        '\\' => escape(&mut chars)?,
+        c => c,
+    };
+
+    if chars.next()? == '\'' {
+        Some((input.len() - chars.as_str().len(), c.to_string()))
+    } else {
+        None
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    // Comments are whitespace to the macro, so they are skipped
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+
+            if trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if trimmed.starts_with("/*") {
+                self.pos += block_comment_len(trimmed);
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn ident(&mut self) -> Option<&'a str> {
+        self.skip_whitespace();
+
+        let rest = self.rest();
+        let len = ident_len(rest);
+
+        if len == 0 {
+            None
+        } else {
+            self.pos += len;
+            Some(&rest[..len])
+        }
+    }
+
+    // This returns the same value as `concat!` does for the literal
+    fn literal(&mut self) -> Option<String> {
+        self.skip_whitespace();
+
+        let rest = self.rest();
+
+        let (len, value) = if let Some(negative) = rest.strip_prefix('-') {
+            let trimmed = negative.trim_start();
+            let (len, value) = number(trimmed)?;
+            (rest.len() - trimmed.len() + len, format!("-{}", value))
+        } else if rest.starts_with(|c: char| c.is_ascii_digit()) {
+            number(rest)?
+        } else if rest.starts_with('"') {
+            string(rest)?
+        } else if rest.starts_with("r\"") || rest.starts_with("r#") {
+            raw_string(rest)?
+        } else if rest.starts_with('\'') {
+            char_literal(rest)?
+        } else {
+            match &rest[..ident_len(rest)] {
+                value @ ("true" | "false") => (value.len(), value.to_owned()),
+                _ => return None,
+            }
+        };
+
+        self.pos += len;
+        Some(value)
+    }
+
+    fn declarations(&mut self, close: char) -> Option<String> {
+        let mut output = String::new();
+
+        while !self.eat(close) {
+            if !self.eat('.') {
+                return None;
+            }
+
+            let method = self.ident()?;
+
+            if !self.eat('(') {
+                return None;
+            }
+
+            let name = self.literal()?;
+
+            if !self.eat(',') {
+                return None;
+            }
+
+            let value = self.literal()?;
+
+            self.eat(',');
+
+            if !self.eat(')') {
+                return None;
+            }
+
+            output.push_str(&declaration(method, &name, &value)?);
+        }
+
+        Some(output)
+    }
+
+    fn open(&mut self) -> Option<char> {
+        if self.eat('{') {
+            Some('}')
+        } else if self.eat('(') {
+            Some(')')
+        } else if self.eat('[') {
+            Some(']')
+        } else {
+            None
+        }
+    }
+
+    fn static_class(&mut self) -> Option<String> {
+        let close = self.open()?;
+
+        let prefix = if self.eat('#') {
+            if !(self.eat('!') && self.eat('[') && self.ident()? == "prefix" && self.eat('=')) {
+                return None;
+            }
+
+            let prefix = self.literal()?;
+
+            if !self.eat(']') {
+                return None;
+            }
+
+            Some(prefix)
+        } else {
+            None
+        };
+
+        let declarations = self.declarations(close)?;
+        let class_name = class_name(prefix.as_deref(), &declarations);
+        Some(class_rule(&class_name, &declarations))
+    }
+
+    fn static_stylesheet(&mut self) -> Option<String> {
+        let close = self.open()?;
+        let selector = self.literal()?;
+
+        if !(self.eat(',') && self.eat('{')) {
+            return None;
+        }
+
+        let declarations = self.declarations('}')?;
+
+        self.eat(',');
+
+        if !self.eat(close) {
+            return None;
+        }
+
+        Some(stylesheet_rule(&selector, &declarations))
+    }
+}
+
+type Parse<'a> = fn(&mut Parser<'a>) -> Option<String>;
+
+// Finds the macro calls, skipping over comments, strings, and chars
+fn find_macros(input: &str) -> Vec<(usize, &'static str, Parse<'_>)> {
+    let mut matches = vec![];
+    let mut pos = 0;
+
+    while let Some(c) = input[pos..].chars().next() {
+        let rest = &input[pos..];
+
+        if rest.starts_with("//") {
+            pos += rest.find('\n').unwrap_or(rest.len());
+        } else if rest.starts_with("/*") {
+            pos += block_comment_len(rest);
+        } else if c == '"' {
+            pos += skip_string(rest, 1);
+        } else if c == '\'' {
+            // This is either a char or a lifetime
+            pos += char_literal(rest).map_or(1, |(len, _)| len);
+        } else if c.is_alphanumeric() || c == '_' {
+            let word = &rest[..ident_len(rest)];
+            let after = &rest[word.len()..];
+
+            pos += word.len();
+
+            match word {
+                // `r#foo` is a raw identifier, so `raw_string` returns `None`
+                "r" | "br" if after.starts_with('"') || after.starts_with('#') => {
+                    pos += raw_string(&rest[word.len() - 1..]).map_or(0, |(len, _)| len - 1);
+                }
+                "b" if after.starts_with('"') => {
+                    pos += skip_string(after, 1);
+                }
+                "static_class" if after.starts_with('!') => {
+                    matches.push((pos + 1, "static_class!", Parser::static_class as Parse<'_>));
+                }
+                "static_stylesheet" if after.starts_with('!') => {
+                    matches.push((
+                        pos + 1,
+                        "static_stylesheet!",
+                        Parser::static_stylesheet as Parse<'_>,
+                    ));
+                }
+                _ => {}
+            }
+        } else {
+            pos += c.len_utf8();
+        }
+    }
+
+    matches
+}
+
+// Returns the length of the string which starts at `start`, this doesn't check the escapes
+fn skip_string(input: &str, start: usize) -> usize {
+    let mut chars = input[start..].chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => {
+                chars.next();
+            }
+            _ => {}
+        }
+    }
+
+    input.len() - chars.as_str().len()
+}
+
+fn extract_str(input: &str, seen: &mut HashSet<String>, output: &mut String) -> Result<(), String> {
+    // The rules are kept in the same order as the source code
+    for (pos, name, parse) in find_macros(input) {
+        let mut parser = Parser { input, pos };
+
+        match parse(&mut parser) {
+            Some(rule) => {
+                if seen.insert(rule.clone()) {
+                    output.push_str(&rule);
+                    output.push('\n');
+                }
+            }
+            // If it was skipped then the class would have no styles, so it is an error instead
+            None => {
+                let line = input[..pos].matches('\n').count() + 1;
+
+                return Err(format!(
+                    "line {}: {} only supports `.style` and `.style_important` with literal arguments",
+                    line, name,
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn rust_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            rust_files(&path, files)?;
+        } else if path.extension() == Some(OsStr::new("rs")) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the CSS for every [`static_class!`](crate::static_class) and
+/// [`static_stylesheet!`](crate::static_stylesheet) in the `.rs` files
+/// inside of `dir` (including sub-directories).
+///
+/// Macro calls inside of comments and strings are ignored.
+///
+/// If a macro call can't be parsed (for example if it is inside of another
+/// macro which passes `$value` to it) then this returns an
+/// [`io::ErrorKind::InvalidData`] error, because otherwise the class would not
+/// have any styles.
+pub fn extract<P>(dir: P) -> io::Result<String>
+where
+    P: AsRef<Path>,
+{
+    let mut files = vec![];
+    rust_files(dir.as_ref(), &mut files)?;
+
+    // This makes the output deterministic
+    files.sort();
+
+    let mut seen = HashSet::new();
+    let mut output = String::new();
+
+    for file in files {
+        // The compiler converts CRLF into LF before parsing
+        let input = fs::read_to_string(&file)?.replace("\r\n", "\n");
+
+        extract_str(&input, &mut seen, &mut output).map_err(|message| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", file.display(), message),
+            )
+        })?;
+    }
+
+    Ok(output)
+}
+
+/// The same as [`extract()`] except it writes the CSS into the file `output`.
+///
+/// The file is only written if the CSS has changed.
+pub fn write<P, O>(dir: P, output: O) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    O: AsRef<Path>,
+{
+    let css = extract(dir)?;
+    let output = output.as_ref();
+
+    if fs::read_to_string(output).ok().as_deref() != Some(css.as_str()) {
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(output, css)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{class_name, extract_str};
+
+    #[test]
+    fn extract() {
+        let mut seen = HashSet::new();
+        let mut output = String::new();
+
+        extract_str(
+            r##"
+            static_class! {
+                #![prefix = "button"]
+                .style("color", "red")
+                .style_important("z-index", 5u32)
+            }
+
+            static_stylesheet!("body", {
+                .style("margin", -1)
+                .style(r#"font-family"#, "\u{41}rial")
+                .style("opacity", 0.50f32)
+            });
+
+            // static_class! { .style_signal("color", signal) }
+
+            /// ```
+            /// static_class! { .style_signal("color", signal) }
+            /// ```
+            fn foo() {
+                let _ = "static_class! { .style_signal(\"color\", signal) }";
+                class! { .style("color", "red") }
+            }
+            "##,
+            &mut seen,
+            &mut output,
+        )
+        .unwrap();
+
+        assert_eq!(
+            output,
+            format!(
+                ".{}{{color:red;z-index:5!important;}}\nbody{{margin:-1;font-family:Arial;opacity:0.50;}}\n",
+                class_name(Some("button"), "color:red;z-index:5!important;"),
+            ),
+        );
+    }
+
+    // The extracted CSS must be the same as the CSS which is generated by the macro
+    #[test]
+    fn literals() {
+        let mut output = String::new();
+
+        extract_str(
+            r#"
+            static_stylesheet!("a", {
+                .style("a", 5u32)
+                .style("b", -1)
+                .style("c", 0x1F)
+                .style("d", 1_000.50f64)
+                .style("e", 1e3)
+                .style("f", '\'')
+                .style("g", true)
+                .style("h", r"\n")
+                .style("i", "\x41\u{1F600}\
+                            b")
+                .style_important("j", - 2.)
+            });
+            "#,
+            &mut HashSet::new(),
+            &mut output,
+        )
+        .unwrap();
+
+        let declarations = concat!(
+            "",
+            crate::__internal_static_style!(style, "a", 5u32),
+            crate::__internal_static_style!(style, "b", -1),
+            crate::__internal_static_style!(style, "c", 0x1F),
+            crate::__internal_static_style!(style, "d", 1_000.50f64),
+            crate::__internal_static_style!(style, "e", 1e3),
+            crate::__internal_static_style!(style, "f", '\''),
+            crate::__internal_static_style!(style, "g", true),
+            crate::__internal_static_style!(style, "h", r"\n"),
+            crate::__internal_static_style!(style, "i", "\x41\u{1F600}b"),
+            crate::__internal_static_style!(style_important, "j", -2.),
+        );
+
+        assert_eq!(output, format!("a{{{}}}\n", declarations));
+    }
+
+    #[test]
+    fn unsupported() {
+        let error = extract_str(
+            r#"
+            static_class! { .style("color", "red") }
+            static_class! { .style_signal("color", signal) }
+            "#,
+            &mut HashSet::new(),
+            &mut String::new(),
+        )
+        .unwrap_err();
+
+        assert!(error.starts_with("line 3: static_class!"));
+    }
+}
@@ -290,3 +290,35 @@ where
         self
     }
 }
+
+/// A typed set of CSS variables, such as a theme.
+///
+/// ```rust
+/// #[derive(Clone)]
+/// struct Theme {
+///     accent: String,
+///     background: String,
+/// }
+///
+/// impl CssVars for Theme {
+///     fn each_var<F>(&self, mut f: F)
+///     where
+///         F: FnMut(&str, &str),
+///     {
+///         f("--accent", &self.accent);
+///         f("--background", &self.background);
+///     }
+/// }
+///
+/// let theme = Mutable::new(Theme { ... });
+///
+/// stylesheet!(":root", {
+///     .css_vars_signal(theme.signal_cloned())
+/// });
+/// ```
+pub trait CssVars {
+    /// Calls `f` with the name and value of each CSS variable.
+    fn each_var<F>(&self, f: F)
+    where
+        F: FnMut(&str, &str);
+}
@@ -1,4 +1,4 @@
-use std::{borrow::Cow, mem::ManuallyDrop};
+use std::{any::Any, borrow::Cow, fmt, mem::ManuallyDrop};
 
 use discard::Discard;
 use wasm_bindgen::{intern, JsValue, UnwrapThrowExt};
@@ -142,6 +142,38 @@ where
     }
 }
 
+#[cfg(panic = "unwind")]
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        String::from(*message)
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("Box<dyn Any>")
+    }
+}
+
+pub(crate) fn report_warning(message: &str) {
+    web_sys::console::warn_1(&JsValue::from(message));
+}
+
+// This is used by `unwrap_js` and `report_js`, so that all errors are reported in the same way
+pub(crate) fn error_message<E>(error: &E) -> String
+where
+    E: fmt::Debug + 'static,
+{
+    use wasm_bindgen::JsCast;
+
+    match (error as &dyn Any)
+        .downcast_ref::<JsValue>()
+        .and_then(|e| e.dyn_ref::<js_sys::Error>())
+    {
+        Some(e) => String::from(e.message()),
+        // TODO test this
+        None => format!("{:?}", error),
+    }
+}
+
 pub(crate) trait UnwrapJsExt<T> {
     fn unwrap_js(self) -> T;
 }
@@ -154,17 +186,7 @@ impl<T> UnwrapJsExt<T> for Result<T, JsValue> {
         match self {
             Ok(value) => value,
             Err(e) => {
-                use wasm_bindgen::JsCast;
-
-                match e.dyn_ref::<js_sys::Error>() {
-                    Some(e) => {
-                        panic!("{}", e.message());
-                    }
-                    // TODO test this
-                    None => {
-                        panic!("{:?}", e);
-                    }
-                }
+                panic!("{}", error_message(&e));
             }
         }
     }
@@ -178,6 +200,16 @@ impl<T> UnwrapJsExt<T> for Result<T, JsValue> {
     }
 }
 
+// This is the same as `unwrap_js` except it logs the error instead of panicking,
+// it is used for errors which are recovered from (such as the error boundaries)
+pub(crate) fn report_js<E>(context: &str, error: &E)
+where
+    E: fmt::Debug + 'static,
+{
+    let message = format!("{}: {}", context, error_message(error));
+    web_sys::console::error_1(&JsValue::from(message));
+}
+
 // This needs to be a macro because #[track_caller] isn't supported in closures
 // https://github.com/rust-lang/rust/issues/87417
 #[doc(hidden)]
@@ -0,0 +1,125 @@
+// This is used by the `vendor-prefixes` feature to try prefixed styles when the
+// unprefixed style isn't supported by the browser
+
+const VENDORS: &[&str] = &["-webkit-", "-moz-", "-ms-"];
+
+const VALUES: &[(&str, &[&str])] = &[
+    ("flex", &["-webkit-flex", "-webkit-box", "-ms-flexbox"]),
+    (
+        "inline-flex",
+        &[
+            "-webkit-inline-flex",
+            "-webkit-inline-box",
+            "-ms-inline-flexbox",
+        ],
+    ),
+    ("grid", &["-ms-grid"]),
+    ("inline-grid", &["-ms-inline-grid"]),
+    ("sticky", &["-webkit-sticky"]),
+    ("fit-content", &["-webkit-fit-content", "-moz-fit-content"]),
+    ("min-content", &["-webkit-min-content", "-moz-min-content"]),
+    ("max-content", &["-webkit-max-content", "-moz-max-content"]),
+];
+
+const FUNCTIONS: &[&str] = &[
+    "calc(",
+    "image-set(",
+    "linear-gradient(",
+    "radial-gradient(",
+    "repeating-linear-gradient(",
+    "repeating-radial-gradient(",
+];
+
+fn values(value: &str) -> Vec<String> {
+    if let Some((_, values)) = VALUES.iter().find(|(x, _)| *x == value) {
+        values.iter().map(|x| x.to_string()).collect()
+    } else if FUNCTIONS.iter().any(|x| value.starts_with(x)) {
+        VENDORS[..2]
+            .iter()
+            .map(|vendor| format!("{}{}", vendor, value))
+            .collect()
+    } else {
+        vec![]
+    }
+}
+
+fn names(name: &str) -> Vec<String> {
+    // Custom properties and already prefixed names are never prefixed
+    if name.starts_with('-') {
+        vec![]
+    } else {
+        VENDORS
+            .iter()
+            .map(|vendor| format!("{}{}", vendor, name))
+            .collect()
+    }
+}
+
+/// Calls `f` with each prefixed variant of the style until `f` returns `Some`.
+///
+/// This does not include the unprefixed style, because it has already been tried.
+pub(crate) fn find_map<A, F>(name: &str, value: &str, mut f: F) -> Option<A>
+where
+    F: FnMut(&str, &str) -> Option<A>,
+{
+    let names = names(name);
+    let values = values(value);
+
+    values
+        .iter()
+        .find_map(|value| f(name, value))
+        .or_else(|| names.iter().find_map(|name| f(name, value)))
+        .or_else(|| {
+            names.iter().find_map(|name| {
+                values
+                    .iter()
+                    // A prefixed name is only combined with a value that has the same prefix
+                    .filter(|value| {
+                        VENDORS
+                            .iter()
+                            .any(|x| name.starts_with(x) && value.starts_with(x))
+                    })
+                    .find_map(|value| f(name, value))
+            })
+        })
+}
+
+/// Calls `f` with each prefixed variant of the style name.
+pub(crate) fn each_name<F>(name: &str, mut f: F)
+where
+    F: FnMut(&str),
+{
+    for name in names(name) {
+        f(&name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_map;
+
+    #[test]
+    fn prefixes() {
+        let mut tried = vec![];
+
+        let _: Option<()> = find_map("display", "flex", |name, value| {
+            tried.push(format!("{}: {}", name, value));
+            None
+        });
+
+        assert_eq!(tried[0], "display: -webkit-flex");
+        assert!(tried.contains(&"-webkit-display: -webkit-box".to_owned()));
+        assert!(!tried.contains(&"-moz-display: -webkit-box".to_owned()));
+
+        let found = find_map("user-select", "none", |name, value| {
+            if name == "-webkit-user-select" {
+                Some(value.to_owned())
+            } else {
+                None
+            }
+        });
+
+        assert_eq!(found.as_deref(), Some("none"));
+        assert_eq!(find_map("--accent", "red", |_, _| Some(())), None);
+    }
+}